@@ -3,22 +3,34 @@ use time::Duration;
 use super::define_build_mode_setter;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct ConsensusConfig {
     /// How long we wait for a proposal block before prevoting nil
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub timeout_propose: Duration,
     /// How much timeout_propose increases with each round
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub timeout_propose_delta: Duration,
     /// How long we wait after receiving +2/3 prevotes for “anything” (ie. not a single block or nil)
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub timeout_prevote: Duration,
     /// How much the timeout_prevote increases with each round
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub timeout_prevote_delta: Duration,
     /// How long we wait after receiving +2/3 precommits for “anything” (ie. not a single block or nil)
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub timeout_precommit: Duration,
     /// How much the timeout_precommit increases with each round
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub timeout_precommit_delta: Duration,
     /// How long we wait after committing a block, before starting on the new
     /// height (this gives us a chance to receive some more precommits, even
     /// though we already have +2/3).
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub timeout_commit: Duration,
 
     /// How many blocks to look back to check existence of the node's consensus votes before joining consensus
@@ -32,10 +44,13 @@ pub struct ConsensusConfig {
 
     /// EmptyBlocks mode and possible interval between empty blocks
     pub create_empty_blocks: bool,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub create_empty_blocks_interval: Duration,
 
     /// Reactor sleep duration parameters
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub peer_gossip_sleep_duration: Duration,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub peer_query_maj23_sleep_duration: Duration,
 
     /// Set to true to discard ABCI responses from the state store, which can save a
@@ -0,0 +1,362 @@
+use std::{env, fmt::Display, str::FromStr};
+
+use time::Duration;
+
+use super::{
+    Config, ConsensusConfig, MempoolConfig, P2PConfig, PeerAddress, PrometheusConfig,
+    StateSyncConfig,
+};
+use crate::{utils, Error, Result};
+
+/// `<prefix>_`, whether `prefix` ends with `_` or not.
+fn normalize_prefix(prefix: &str) -> String {
+    format!("{}_", prefix.trim_end_matches('_'))
+}
+
+/// Value of an environment variable
+struct EnvVar<'a> {
+    name: &'a str,
+    value: &'a str,
+}
+
+impl EnvVar<'_> {
+    fn invalid(&self, reason: impl Display) -> Error {
+        Error::InvalidEnvVar(String::from(self.name), reason.to_string())
+    }
+
+    fn string(&self) -> String {
+        String::from(self.value)
+    }
+
+    fn parse<T>(&self) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.value.parse().map_err(|e| self.invalid(e))
+    }
+
+    fn duration(&self) -> Result<Duration> {
+        utils::parse_go_duration(self.value).map_err(|e| self.invalid(e))
+    }
+
+    /// Comma separated, empty items are ignored.
+    fn list(&self) -> Vec<String> {
+        self.value
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    fn peers(&self) -> Result<Vec<PeerAddress>> {
+        self.list()
+            .iter()
+            .map(|p| p.parse().map_err(|e| self.invalid(e)))
+            .collect()
+    }
+}
+
+impl Config {
+    /// Overlay fields by environment variables, see `apply_vars`.
+    pub fn apply_env(self, prefix: &str) -> Result<Self> {
+        let prefix = normalize_prefix(prefix);
+        let mut vars = Vec::new();
+
+        for (name, value) in env::vars_os() {
+            if let (Some(name), Some(value)) = (name.to_str(), value.to_str()) {
+                vars.push((String::from(name), String::from(value)));
+            } else if name.to_string_lossy().starts_with(&prefix) {
+                return Err(Error::InvalidEnvVar(
+                    name.to_string_lossy().into_owned(),
+                    String::from("not unicode"),
+                ));
+            }
+        }
+
+        self.apply_vars(&prefix, vars)
+    }
+
+    /// Overlay fields by variables named `<prefix>_<FIELD>` or
+    /// `<prefix>_<SECTION>_<FIELD>`, like `EMBEDDED_TD_DB_BACKEND=rocksdb` or
+    /// `EMBEDDED_TD_CONSENSUS_TIMEOUT_COMMIT=500ms`. Variables without prefix
+    /// are ignored.
+    ///
+    /// Durations are golang durations, lists are comma separated. Any field
+    /// of `STATE_SYNC` or `PROMETHEUS` enables it, `<SECTION>_ENABLE=false`
+    /// disables it. `FAST_SYNC=off` disables fast sync.
+    pub fn apply_vars(
+        self,
+        prefix: &str,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Result<Self> {
+        let prefix = normalize_prefix(prefix);
+
+        let mut vars: Vec<_> = vars
+            .into_iter()
+            .filter(|(name, _)| name.starts_with(&prefix))
+            .collect();
+        vars.sort();
+
+        let mut this = self;
+        let mut state_sync_enable = None;
+        let mut prometheus_enable = None;
+
+        for (name, value) in &vars {
+            let var = EnvVar { name, value };
+            let key = name[prefix.len()..].to_ascii_lowercase();
+
+            let known = if let Some(field) = key.strip_prefix("p2p_") {
+                set_p2p(&mut this.p2p, field, &var)?
+            } else if let Some(field) = key.strip_prefix("mempool_") {
+                set_mempool(&mut this.mempool, field, &var)?
+            } else if let Some(field) = key.strip_prefix("consensus_") {
+                set_consensus(&mut this.consensus, field, &var)?
+            } else if let Some(field) = key.strip_prefix("state_sync_") {
+                if field == "enable" {
+                    state_sync_enable = Some(var.parse()?);
+                    true
+                } else {
+                    let state_sync = this.state_sync.get_or_insert_with(Default::default);
+                    set_state_sync(state_sync, field, &var)?
+                }
+            } else if let Some(field) = key.strip_prefix("prometheus_") {
+                if field == "enable" {
+                    prometheus_enable = Some(var.parse()?);
+                    true
+                } else {
+                    let prometheus = this.prometheus.get_or_insert_with(Default::default);
+                    set_prometheus(prometheus, field, &var)?
+                }
+            } else {
+                set_config(&mut this, &key, &var)?
+            };
+
+            if !known {
+                return Err(Error::UnknownEnvVar(name.clone()));
+            }
+        }
+
+        match state_sync_enable {
+            Some(false) => this.state_sync = None,
+            Some(true) if this.state_sync.is_none() => this.state_sync = Some(Default::default()),
+            _ => {}
+        }
+
+        match prometheus_enable {
+            Some(false) => this.prometheus = None,
+            Some(true) if this.prometheus.is_none() => this.prometheus = Some(Default::default()),
+            _ => {}
+        }
+
+        Ok(this)
+    }
+}
+
+fn set_config(c: &mut Config, field: &str, var: &EnvVar) -> Result<bool> {
+    match field {
+        "log_level" => c.log_level = var.parse()?,
+        "moniker" => c.moniker = var.string(),
+        "db_backend" => c.db_backend = var.parse()?,
+        "log_format" => c.log_format = var.parse()?,
+        "priv_validator_laddr" => c.priv_validator_laddr = var.string(),
+        "builtin_signer" => c.builtin_signer = var.parse()?,
+        "filter_peers" => c.filter_peers = var.parse()?,
+        "pprof_laddr" => c.pprof_laddr = var.string(),
+        "rpc_laddr" => c.rpc_laddr = var.string(),
+        "port_strategy" => c.port_strategy = var.parse()?,
+        "data_dir" => c.data_dir = var.string(),
+        "fast_sync" if var.value.eq_ignore_ascii_case("off") => c.fast_sync = None,
+        "fast_sync" => c.fast_sync = Some(var.parse()?),
+        "tx_index" => c.tx_index = var.parse()?,
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+fn set_p2p(c: &mut P2PConfig, field: &str, var: &EnvVar) -> Result<bool> {
+    match field {
+        "laddr" => c.laddr = var.string(),
+        "external_address" => c.external_address = var.string(),
+        "seeds" => c.seeds = var.peers()?,
+        "persistent_peers" => c.persistent_peers = var.peers()?,
+        "upnp" => c.upnp = var.parse()?,
+        "local_net" => c.local_net = var.parse()?,
+        "max_num_inbound_peers" => c.max_num_inbound_peers = var.parse()?,
+        "max_num_outbound_peers" => c.max_num_outbound_peers = var.parse()?,
+        "unconditional_peer_ids" => c.unconditional_peer_ids = var.list(),
+        "persistent_peers_max_dial_period" => {
+            c.persistent_peers_max_dial_period = var.duration()?
+        }
+        "flush_throttle_timeout" => c.flush_throttle_timeout = var.duration()?,
+        "max_packet_msg_payload_size" => c.max_packet_msg_payload_size = var.parse()?,
+        "send_rate" => c.send_rate = var.parse()?,
+        "recv_rate" => c.recv_rate = var.parse()?,
+        "pex" => c.pex = var.parse()?,
+        "seed_mode" => c.seed_mode = var.parse()?,
+        "private_peer_ids" => c.private_peer_ids = var.list(),
+        "allow_duplicate_ip" => c.allow_duplicate_ip = var.parse()?,
+        "handshake_timeout" => c.handshake_timeout = var.duration()?,
+        "dial_timeout" => c.dial_timeout = var.duration()?,
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+fn set_mempool(c: &mut MempoolConfig, field: &str, var: &EnvVar) -> Result<bool> {
+    match field {
+        "version" => c.version = var.parse()?,
+        "wal_dir" => c.wal_dir = var.string(),
+        "recheck" => c.recheck = var.parse()?,
+        "broadcast" => c.broadcast = var.parse()?,
+        "size" => c.size = var.parse()?,
+        "max_txs_bytes" => c.max_txs_bytes = var.parse()?,
+        "cache_size" => c.cache_size = var.parse()?,
+        "keep_invalid_txs_in_cache" => c.keep_invalid_txs_in_cache = var.parse()?,
+        "max_tx_bytes" => c.max_tx_bytes = var.parse()?,
+        "max_batch_bytes" => c.max_batch_bytes = var.parse()?,
+        "ttl_duration" => c.ttl_duration = var.duration()?,
+        "ttl_num_blocks" => c.ttl_num_blocks = var.parse()?,
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+fn set_consensus(c: &mut ConsensusConfig, field: &str, var: &EnvVar) -> Result<bool> {
+    match field {
+        "timeout_propose" => c.timeout_propose = var.duration()?,
+        "timeout_propose_delta" => c.timeout_propose_delta = var.duration()?,
+        "timeout_prevote" => c.timeout_prevote = var.duration()?,
+        "timeout_prevote_delta" => c.timeout_prevote_delta = var.duration()?,
+        "timeout_precommit" => c.timeout_precommit = var.duration()?,
+        "timeout_precommit_delta" => c.timeout_precommit_delta = var.duration()?,
+        "timeout_commit" => c.timeout_commit = var.duration()?,
+        "double_sign_check_height" => c.double_sign_check_height = var.parse()?,
+        "skip_timeout_commit" => c.skip_timeout_commit = var.parse()?,
+        "create_empty_blocks" => c.create_empty_blocks = var.parse()?,
+        "create_empty_blocks_interval" => c.create_empty_blocks_interval = var.duration()?,
+        "peer_gossip_sleep_duration" => c.peer_gossip_sleep_duration = var.duration()?,
+        "peer_query_maj23_sleep_duration" => c.peer_query_maj23_sleep_duration = var.duration()?,
+        "discard_abci_responses" => c.discard_abci_responses = var.parse()?,
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+fn set_state_sync(c: &mut StateSyncConfig, field: &str, var: &EnvVar) -> Result<bool> {
+    match field {
+        "rpc_servers" => c.rpc_servers = var.list(),
+        "trust_height" => c.trust_height = var.parse()?,
+        "trust_hash" => c.trust_hash = var.string(),
+        "trust_period" => c.trust_period = var.duration()?,
+        "discovery_time" => c.discovery_time = var.duration()?,
+        "temp_dir" => c.temp_dir = var.string(),
+        "chunk_request_timeout" => c.chunk_request_timeout = var.duration()?,
+        "chunk_fetchers" => c.chunk_fetchers = var.parse()?,
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+fn set_prometheus(c: &mut PrometheusConfig, field: &str, var: &EnvVar) -> Result<bool> {
+    match field {
+        "prometheus_listen_addr" => c.prometheus_listen_addr = var.string(),
+        "max_open_connections" => c.max_open_connections = var.parse()?,
+        "namespace" => c.namespace = var.string(),
+        _ => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Duration;
+
+    use super::Config;
+    use crate::Error;
+
+    fn vars(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(n, v)| (String::from(*n), String::from(*v)))
+            .collect()
+    }
+
+    #[test]
+    fn test_apply_vars() {
+        let peer = "0123456789abcdef0123456789abcdef01234567@127.0.0.1:26656";
+
+        let config = Config::default()
+            .apply_vars(
+                "EMBEDDED_TD",
+                vars(&[
+                    ("EMBEDDED_TD_DB_BACKEND", "rocksdb"),
+                    ("EMBEDDED_TD_CONSENSUS_TIMEOUT_COMMIT", "500ms"),
+                    ("EMBEDDED_TD_P2P_SEEDS", &format!("{}, {}", peer, peer)),
+                    ("EMBEDDED_TD_STATE_SYNC_RPC_SERVERS", "a:26657,b:26657"),
+                    ("EMBEDDED_TD_FAST_SYNC", "off"),
+                    ("OTHER_MONIKER", "ignored"),
+                ]),
+            )
+            .unwrap();
+
+        assert_eq!(config.db_backend.to_str(), "rocksdb");
+        assert_eq!(config.consensus.timeout_commit, Duration::milliseconds(500));
+        assert_eq!(config.p2p.seeds.len(), 2);
+        assert_eq!(config.p2p.seeds[0].to_string(), peer);
+        assert_eq!(
+            config.state_sync.unwrap().rpc_servers,
+            vec!["a:26657", "b:26657"]
+        );
+        assert!(config.fast_sync.is_none());
+        assert_eq!(config.moniker, Config::default().moniker);
+
+        let config = Config::default()
+            .apply_vars(
+                "EMBEDDED_TD",
+                vars(&[
+                    ("EMBEDDED_TD_PROMETHEUS_NAMESPACE", "node"),
+                    ("EMBEDDED_TD_PROMETHEUS_ENABLE", "false"),
+                ]),
+            )
+            .unwrap();
+        assert!(config.prometheus.is_none());
+
+        let err = Config::default()
+            .apply_vars("EMBEDDED_TD", vars(&[("EMBEDDED_TD_P2P_LADR", "x")]))
+            .unwrap_err();
+        assert!(matches!(err, Error::UnknownEnvVar(n) if n == "EMBEDDED_TD_P2P_LADR"));
+
+        let err = Config::default()
+            .apply_vars(
+                "EMBEDDED_TD",
+                vars(&[("EMBEDDED_TD_CONSENSUS_TIMEOUT_COMMIT", "500")]),
+            )
+            .unwrap_err();
+        assert!(
+            matches!(err, Error::InvalidEnvVar(n, _) if n == "EMBEDDED_TD_CONSENSUS_TIMEOUT_COMMIT")
+        );
+    }
+    #[cfg(unix)]
+    #[test]
+    fn test_apply_env_not_unicode() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        std::env::set_var("EMBEDDED_TDX_FOO", OsStr::from_bytes(b"\xff"));
+
+        // Only variables under `<prefix>_` are checked.
+        assert!(Config::default().apply_env("EMBEDDED_TD").is_ok());
+        assert!(matches!(
+            Config::default().apply_env("EMBEDDED_TDX"),
+            Err(Error::InvalidEnvVar(..))
+        ));
+
+        std::env::remove_var("EMBEDDED_TDX_FOO");
+    }
+}
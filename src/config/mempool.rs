@@ -1,6 +1,7 @@
 use time::Duration;
 
 use super::{define_build_mode_setter, define_to_str_for_enum};
+use crate::{defined, Error, Result};
 
 #[derive(Debug, Clone, Default)]
 pub enum MempoolVersion {
@@ -16,10 +17,19 @@ define_to_str_for_enum!(
 );
 
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct MempoolConfig {
     /// Mempool version
     pub version: MempoolVersion,
 
+    /// Directory of mempool WAL, relative to home if not absolute. Empty to
+    /// disable WAL.
+    pub wal_dir: String,
+
     /// Recheck tx.
     pub recheck: bool,
 
@@ -42,18 +52,27 @@ pub struct MempoolConfig {
     /// again in the future.
     pub keep_invalid_txs_in_cache: bool,
 
+    /// Maximum size of a batch of transactions to send to a peer, including
+    /// all encoding overheads. 0 is unlimited.
+    pub max_batch_bytes: u64,
+
     /// Maximum size of a single transaction.
     /// NOTE: the max size of a tx transmitted over the network is {max_tx_bytes}.
     pub max_tx_bytes: u64,
 
+    /// Only for priority mempool (v1).
+    ///
     /// ttl-duration, if non-zero, defines the maximum amount of time a transaction
     /// can exist for in the mempool.
     ///
     /// Note, if ttl-num-blocks is also defined, a transaction will be removed if it
     /// has existed in the mempool at least ttl-num-blocks number of blocks or if it's
     /// insertion time into the mempool is beyond ttl-duration.
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub ttl_duration: Duration,
 
+    /// Only for priority mempool (v1).
+    ///
     /// ttl-num-blocks, if non-zero, defines the maximum number of blocks a transaction
     /// can exist for in the mempool.
     ///
@@ -67,6 +86,7 @@ impl Default for MempoolConfig {
     fn default() -> Self {
         Self {
             version: Default::default(),
+            wal_dir: Default::default(),
             recheck: true,
             broadcast: true,
             size: 5000,
@@ -74,6 +94,7 @@ impl Default for MempoolConfig {
             cache_size: 10000,
             keep_invalid_txs_in_cache: false,
             max_tx_bytes: 1048576,
+            max_batch_bytes: 0,
             ttl_duration: Duration::new(0, 0),
             ttl_num_blocks: 0,
         }
@@ -87,10 +108,16 @@ impl MempoolConfig {
 
     define_build_mode_setter!(broadcast, bool);
 
+    define_build_mode_setter!(wal_dir, str);
+
     define_build_mode_setter!(size, u64);
 
+    define_build_mode_setter!(max_txs_bytes, u64);
+
     define_build_mode_setter!(max_tx_bytes, u64);
 
+    define_build_mode_setter!(max_batch_bytes, u64);
+
     define_build_mode_setter!(cache_size, u64);
 
     define_build_mode_setter!(keep_invalid_txs_in_cache, bool);
@@ -98,4 +125,86 @@ impl MempoolConfig {
     define_build_mode_setter!(ttl_duration, Duration);
 
     define_build_mode_setter!(ttl_num_blocks, u64);
+
+    /// Options of priority mempool (v1), switch version to it.
+    pub fn priority(self, ttl_duration: Duration, ttl_num_blocks: u64) -> Self {
+        Self {
+            version: MempoolVersion::Priority,
+            ttl_duration,
+            ttl_num_blocks,
+            ..self
+        }
+    }
+
+    /// Check limits, and options only for priority mempool aren't used with
+    /// FIFO mempool.
+    pub fn validate(&self) -> Result<()> {
+        if self.max_tx_bytes > self.max_txs_bytes {
+            return Err(Error::InvalidConfig(format!(
+                "mempool max_tx_bytes {} is larger than max_txs_bytes {}",
+                self.max_tx_bytes, self.max_txs_bytes
+            )));
+        }
+
+        if matches!(self.version, MempoolVersion::Priority) && !defined::PRIORITY_MEMPOOL {
+            return Err(Error::InvalidConfig(String::from(
+                "priority mempool (v1) needs tendermint 0.34 or later",
+            )));
+        }
+
+        if matches!(self.version, MempoolVersion::FIFO)
+            && (!self.ttl_duration.is_zero() || self.ttl_num_blocks != 0)
+        {
+            return Err(Error::InvalidConfig(String::from(
+                "mempool ttl_duration and ttl_num_blocks need priority mempool (v1)",
+            )));
+        }
+
+        if self.ttl_duration.is_negative() {
+            return Err(Error::InvalidConfig(String::from(
+                "mempool ttl_duration is negative",
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Duration;
+
+    use super::{MempoolConfig, MempoolVersion};
+    use crate::Config;
+
+    #[test]
+    fn test_validate() {
+        assert!(MempoolConfig::default().validate().is_ok());
+
+        let config = MempoolConfig::default().max_txs_bytes(1024);
+        assert!(config.validate().is_err());
+
+        let config = MempoolConfig::default().ttl_num_blocks(10);
+        assert!(config.validate().is_err());
+
+        let config = MempoolConfig::default().priority(Duration::seconds(30), 10);
+        assert!(matches!(config.version, MempoolVersion::Priority));
+        assert_eq!(config.validate().is_ok(), crate::defined::PRIORITY_MEMPOOL);
+    }
+
+    #[test]
+    fn test_wal_dir() {
+        let wal_dir = |dir: &str| {
+            Config::default()
+                .mempool(MempoolConfig::default().wal_dir(dir))
+                .into_model("/home")
+                .unwrap()
+                .mempool
+                .wal_dir
+        };
+
+        assert_eq!(wal_dir(""), "");
+        assert_eq!(wal_dir("data/mempool.wal"), "/home/data/mempool.wal");
+        assert_eq!(wal_dir("/var/mempool.wal"), "/var/mempool.wal");
+    }
 }
@@ -12,6 +12,9 @@ pub use db_backend::*;
 mod p2p;
 pub use p2p::*;
 
+mod peer_address;
+pub use peer_address::*;
+
 mod mempool;
 pub use mempool::*;
 
@@ -27,10 +30,28 @@ pub use tx_index::*;
 mod prometheus;
 pub use prometheus::*;
 
-use crate::{defined, model};
+mod port;
+pub use port::*;
+
+mod sentry;
+pub use sentry::*;
+
+mod preset;
+pub use preset::*;
+
+mod env;
+
+use std::{fmt, path::Path};
+
+use crate::{defined, model, utils::format_go_duration, Error, Result};
 
 /// Config for tendermint
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct Config {
     /// Log level
     pub log_level: LogLevel,
@@ -48,6 +69,11 @@ pub struct Config {
     /// connections from an external PrivValidator process
     pub priv_validator_laddr: String,
 
+    /// Run built-in remote signer with validator key, key file isn't written
+    /// to disk. If `priv_validator_laddr` is empty, a unix socket in work dir
+    /// is used.
+    pub builtin_signer: bool,
+
     /// If true, query the ABCI app on connecting to a new peer
     /// so the app can decide if we should keep the connection or not
     pub filter_peers: bool,
@@ -55,6 +81,14 @@ pub struct Config {
     /// pprof listen addr. Useful to debug tendermint.
     pub pprof_laddr: String,
 
+    /// RPC listen addr, like `tcp://127.0.0.1:26657`. If empty, a unix socket
+    /// in work dir is used.
+    pub rpc_laddr: String,
+
+    /// How to choose ports of P2P, RPC, pprof and Prometheus listen addresses.
+    /// Default is using the configured ports.
+    pub port_strategy: PortStrategy,
+
     /// Data dis
     pub data_dir: String,
 
@@ -105,8 +139,11 @@ impl Default for Config {
             db_backend: Default::default(),
             log_level: Default::default(),
             priv_validator_laddr: Default::default(),
+            builtin_signer: false,
             filter_peers: false,
             pprof_laddr: Default::default(),
+            rpc_laddr: Default::default(),
+            port_strategy: Default::default(),
             p2p: Default::default(),
             mempool: Default::default(),
             state_sync: None,
@@ -128,6 +165,33 @@ impl Config {
     }
 }
 
+/// Error of parsing config enum from string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseEnumError {
+    /// Name of enum
+    pub ty: &'static str,
+
+    /// Input string
+    pub value: String,
+
+    /// Accepted values
+    pub expected: &'static [&'static str],
+}
+
+impl fmt::Display for ParseEnumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid {}: {}, expected one of {}",
+            self.ty,
+            self.value,
+            self.expected.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for ParseEnumError {}
+
 macro_rules! define_to_str_for_enum {
     ($e:ident, $( $key:ident => $value:expr ),*) => {
         impl $e {
@@ -136,12 +200,80 @@ macro_rules! define_to_str_for_enum {
                     $( Self::$key => $value, )*
                 }
             }
+
+            /// All variants.
+            pub fn all() -> impl Iterator<Item = Self> {
+                [$( Self::$key ),*].into_iter()
+            }
+        }
+
+        impl std::fmt::Display for $e {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.to_str())
+            }
         }
+
+        impl std::str::FromStr for $e {
+            type Err = $crate::config::ParseEnumError;
+
+            /// Case insensitive.
+            fn from_str(s: &str) -> std::result::Result<Self, $crate::config::ParseEnumError> {
+                $(
+                    if s.eq_ignore_ascii_case($value) {
+                        return Ok(Self::$key);
+                    }
+                )*
+
+                Err($crate::config::ParseEnumError {
+                    ty: stringify!($e),
+                    value: String::from(s),
+                    expected: &[$( $value ),*],
+                })
+            }
+        }
+
+        impl TryFrom<&str> for $e {
+            type Error = $crate::config::ParseEnumError;
+
+            fn try_from(s: &str) -> std::result::Result<Self, $crate::config::ParseEnumError> {
+                s.parse()
+            }
+        }
+
+        $crate::config::define_serde_by_str!($e);
     };
 }
 
 pub(crate) use define_to_str_for_enum;
 
+/// Serde as string by `Display` and `FromStr`.
+macro_rules! define_serde_by_str {
+    ($t:ty) => {
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $t {
+            fn serialize<S: serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $t {
+            fn deserialize<D: serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<Self, D::Error> {
+                let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+
+                s.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+pub(crate) use define_serde_by_str;
+
 macro_rules! define_build_mode_setter {
     ($name:ident, str) => {
         pub fn $name(self, $name: &str) -> Self {
@@ -179,8 +311,14 @@ impl Config {
 
     define_build_mode_setter!(priv_validator_laddr, str);
 
+    define_build_mode_setter!(builtin_signer, bool);
+
     define_build_mode_setter!(filter_peers, bool);
 
+    define_build_mode_setter!(rpc_laddr, str);
+
+    define_build_mode_setter!(port_strategy, PortStrategy);
+
     define_build_mode_setter!(p2p, P2PConfig);
 
     define_build_mode_setter!(mempool, MempoolConfig);
@@ -197,7 +335,28 @@ impl Config {
 }
 
 impl Config {
-    pub(crate) fn into_model(self, base_dir: &str) -> model::Config {
+    /// Check config before start.
+    pub fn validate(&self) -> Result<()> {
+        self.mempool.validate()?;
+
+        if let Some(state_sync) = &self.state_sync {
+            state_sync.validate()?;
+        }
+
+        if self.builtin_signer && !defined::BUILTIN_SIGNER {
+            return Err(Error::InvalidConfig(String::from(
+                "built-in signer needs tendermint 0.34 or later",
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn into_model(self, base_dir: &str) -> Result<model::Config> {
+        self.validate()?;
+
+        let mut used_ports = Vec::new();
+
         let db_dir = if self.data_dir.is_empty() {
             format!("{}/{}", base_dir, defined::DATA_DIR)
         } else {
@@ -205,7 +364,15 @@ impl Config {
         };
 
         let rpc = {
-            let laddr = format!("unix://{}/{}", base_dir, defined::RPC_UNIX_SOCKET_FILE);
+            let laddr = if self.rpc_laddr.is_empty() {
+                format!("unix://{}/{}", base_dir, defined::RPC_UNIX_SOCKET_FILE)
+            } else {
+                self.port_strategy.apply(self.rpc_laddr, &mut used_ports)?
+            };
+
+            let pprof_laddr = self
+                .port_strategy
+                .apply(self.pprof_laddr, &mut used_ports)?;
 
             model::Rpc {
                 laddr,
@@ -225,35 +392,29 @@ impl Config {
                 max_header_bytes: 1048576,
                 tls_key_file: Default::default(),
                 tls_cert_file: Default::default(),
-                pprof_laddr: self.pprof_laddr,
+                pprof_laddr,
                 grpc_max_open_connections: 900,
             }
         };
 
         let p2p = {
             let addr_book_file = format!("{}/{}", base_dir, defined::ADDR_BOOK_FILE);
-            let persistent_peers_max_dial_period = format!(
-                "{}s",
-                self.p2p.persistent_peers_max_dial_period.whole_seconds()
-            );
-            let flush_throttle_timeout =
-                format!("{}ms", self.p2p.flush_throttle_timeout.whole_milliseconds());
-            let handshake_timeout = format!("{}s", self.p2p.handshake_timeout.whole_seconds());
-            let dial_timeout = format!("{}s", self.p2p.dial_timeout.whole_seconds());
 
             model::P2P {
-                laddr: self.p2p.laddr,
+                laddr: self.port_strategy.apply(self.p2p.laddr, &mut used_ports)?,
                 external_address: self.p2p.external_address,
-                seeds: self.p2p.seeds.join(","),
-                persistent_peers: self.p2p.persistent_peers.join(","),
+                seeds: utils::join_peers(&self.p2p.seeds),
+                persistent_peers: utils::join_peers(&self.p2p.persistent_peers),
                 upnp: self.p2p.upnp,
                 addr_book_file,
                 addr_book_strict: !self.p2p.local_net,
                 max_num_inbound_peers: self.p2p.max_num_inbound_peers,
                 max_num_outbound_peers: self.p2p.max_num_outbound_peers,
                 unconditional_peer_ids: self.p2p.unconditional_peer_ids.join(","),
-                persistent_peers_max_dial_period,
-                flush_throttle_timeout,
+                persistent_peers_max_dial_period: format_go_duration(
+                    self.p2p.persistent_peers_max_dial_period,
+                ),
+                flush_throttle_timeout: format_go_duration(self.p2p.flush_throttle_timeout),
                 max_packet_msg_payload_size: self.p2p.max_packet_msg_payload_size,
                 send_rate: self.p2p.send_rate,
                 recv_rate: self.p2p.recv_rate,
@@ -261,24 +422,30 @@ impl Config {
                 seed_mode: self.p2p.seed_mode,
                 private_peer_ids: self.p2p.private_peer_ids.join(","),
                 allow_duplicate_ip: self.p2p.allow_duplicate_ip,
-                handshake_timeout,
-                dial_timeout,
+                handshake_timeout: format_go_duration(self.p2p.handshake_timeout),
+                dial_timeout: format_go_duration(self.p2p.dial_timeout),
             }
         };
 
         let mempool = {
-            let ttl_duration = format!("{}s", self.mempool.ttl_duration.whole_seconds());
+            let wal_dir = if self.mempool.wal_dir.is_empty()
+                || Path::new(&self.mempool.wal_dir).is_absolute()
+            {
+                self.mempool.wal_dir
+            } else {
+                format!("{}/{}", base_dir, self.mempool.wal_dir)
+            };
 
             model::Mempool {
                 version: String::from(self.mempool.version.to_str()),
-                wal_dir: Default::default(),
+                wal_dir,
                 size: self.mempool.size,
                 max_tx_bytes: self.mempool.max_tx_bytes,
                 cache_size: self.mempool.cache_size,
                 keep_invalid_txs_in_cache: self.mempool.keep_invalid_txs_in_cache,
                 max_txs_bytes: self.mempool.max_txs_bytes,
-                max_batch_bytes: 0,
-                ttl_duration,
+                max_batch_bytes: self.mempool.max_batch_bytes,
+                ttl_duration: format_go_duration(self.mempool.ttl_duration),
                 ttl_num_blocks: self.mempool.ttl_num_blocks,
                 recheck: self.mempool.recheck,
                 broadcast: self.mempool.broadcast,
@@ -292,22 +459,23 @@ impl Config {
                 (false, Default::default())
             };
 
-            let trust_period = format!("{}s", state_sync.trust_period.whole_seconds());
-
-            let discovery_time = format!("{}s", state_sync.discovery_time.whole_seconds());
-
-            let chunk_request_timeout =
-                format!("{}s", state_sync.chunk_request_timeout.whole_seconds());
+            let temp_dir = if state_sync.temp_dir.is_empty() {
+                format!("{}/{}", base_dir, defined::STATE_SYNC_TEMP_DIR)
+            } else if Path::new(&state_sync.temp_dir).is_absolute() {
+                state_sync.temp_dir
+            } else {
+                format!("{}/{}", base_dir, state_sync.temp_dir)
+            };
 
             model::StateSync {
                 enable,
                 rpc_servers: state_sync.rpc_servers.join(","),
                 trust_hash: state_sync.trust_hash,
                 trust_height: state_sync.trust_height,
-                trust_period,
-                discovery_time,
-                temp_dir: Default::default(),
-                chunk_request_timeout,
+                trust_period: format_go_duration(state_sync.trust_period),
+                discovery_time: format_go_duration(state_sync.discovery_time),
+                temp_dir,
+                chunk_request_timeout: format_go_duration(state_sync.chunk_request_timeout),
                 chunk_fetchers: format!("{}", state_sync.chunk_fetchers),
             }
         };
@@ -329,29 +497,23 @@ impl Config {
 
             model::Consensus {
                 wal_file,
-                timeout_propose: utils::build_duration_ms(self.consensus.timeout_propose),
-                timeout_propose_delta: utils::build_duration_ms(
-                    self.consensus.timeout_propose_delta,
-                ),
-                timeout_prevote: utils::build_duration_ms(self.consensus.timeout_prevote),
-                timeout_prevote_delta: utils::build_duration_ms(
-                    self.consensus.timeout_prevote_delta,
-                ),
-                timeout_precommit: utils::build_duration_ms(self.consensus.timeout_precommit),
-                timeout_precommit_delta: utils::build_duration_ms(
-                    self.consensus.timeout_precommit_delta,
-                ),
-                timeout_commit: utils::build_duration_ms(self.consensus.timeout_commit),
+                timeout_propose: format_go_duration(self.consensus.timeout_propose),
+                timeout_propose_delta: format_go_duration(self.consensus.timeout_propose_delta),
+                timeout_prevote: format_go_duration(self.consensus.timeout_prevote),
+                timeout_prevote_delta: format_go_duration(self.consensus.timeout_prevote_delta),
+                timeout_precommit: format_go_duration(self.consensus.timeout_precommit),
+                timeout_precommit_delta: format_go_duration(self.consensus.timeout_precommit_delta),
+                timeout_commit: format_go_duration(self.consensus.timeout_commit),
                 double_sign_check_height: self.consensus.double_sign_check_height,
                 skip_timeout_commit: self.consensus.skip_timeout_commit,
                 create_empty_blocks: self.consensus.create_empty_blocks,
-                create_empty_blocks_interval: utils::build_duration_ms(
+                create_empty_blocks_interval: format_go_duration(
                     self.consensus.create_empty_blocks_interval,
                 ),
-                peer_gossip_sleep_duration: utils::build_duration_ms(
+                peer_gossip_sleep_duration: format_go_duration(
                     self.consensus.peer_gossip_sleep_duration,
                 ),
-                peer_query_maj23_sleep_duration: utils::build_duration_ms(
+                peer_query_maj23_sleep_duration: format_go_duration(
                     self.consensus.peer_query_maj23_sleep_duration,
                 ),
                 discard_abci_responses: self.consensus.discard_abci_responses,
@@ -380,9 +542,16 @@ impl Config {
                 (false, Default::default())
             };
 
+            let prometheus_listen_addr = if enable {
+                self.port_strategy
+                    .apply(prometheus.prometheus_listen_addr, &mut used_ports)?
+            } else {
+                prometheus.prometheus_listen_addr
+            };
+
             model::Instrumentation {
                 prometheus: enable,
-                prometheus_listen_addr: prometheus.prometheus_listen_addr,
+                prometheus_listen_addr,
                 max_open_connections: prometheus.max_open_connections,
                 namespace: prometheus.namespace,
             }
@@ -399,7 +568,13 @@ impl Config {
 
         let node_key_file = format!("{}/{}", base_dir, defined::NODE_KEY_FILE);
 
-        model::Config {
+        let priv_validator_laddr = if self.builtin_signer && self.priv_validator_laddr.is_empty() {
+            format!("unix://{}/{}", base_dir, defined::PRIVVAL_UNIX_SOCKET_FILE)
+        } else {
+            self.priv_validator_laddr
+        };
+
+        Ok(model::Config {
             proxy_app,
             moniker: self.moniker,
             fast_sync,
@@ -410,7 +585,7 @@ impl Config {
             genesis_file,
             priv_validator_key_file,
             priv_validator_state_file,
-            priv_validator_laddr: self.priv_validator_laddr,
+            priv_validator_laddr,
             node_key_file,
             abci: String::from("socket"),
             filter_peers: self.filter_peers,
@@ -422,18 +597,115 @@ impl Config {
             consensus,
             tx_index,
             instrumentation: prometheus,
-        }
+        })
     }
 }
 
 mod utils {
+    use super::PeerAddress;
+
+    pub fn join_peers(peers: &[PeerAddress]) -> String {
+        peers
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+#[cfg(test)]
+mod tests {
     use time::Duration;
 
-    /*     pub fn build_duration_s(d: Duration) -> String { */
-    /*     format!("{}s", d.whole_seconds()) */
-    /* } */
-    /*  */
-    pub fn build_duration_ms(d: Duration) -> String {
-        format!("{}ms", d.whole_milliseconds())
+    use super::{
+        Config, ConsensusConfig, DbBackend, FastSyncVersion, LogFormat, LogLevel, MempoolVersion,
+        P2PConfig, TxIndexConfig,
+    };
+
+    #[test]
+    fn test_parse_enums() {
+        for v in DbBackend::all() {
+            assert_eq!(
+                v.to_string().parse::<DbBackend>().unwrap().to_str(),
+                v.to_str()
+            );
+        }
+        assert_eq!(LogLevel::all().count(), 4);
+        assert_eq!(LogFormat::all().count(), 2);
+        assert_eq!(FastSyncVersion::all().count(), 3);
+
+        assert!(matches!("RocksDB".parse(), Ok(DbBackend::RocksDB)));
+        assert!(matches!(
+            MempoolVersion::try_from("v1"),
+            Ok(MempoolVersion::Priority)
+        ));
+
+        let err = "trace".parse::<LogLevel>().unwrap_err();
+        assert_eq!(err.ty, "LogLevel");
+        assert_eq!(err.value, "trace");
+        assert_eq!(err.expected, &["info", "debug", "warn", "error"]);
+
+        let psql = "psql:postgresql://localhost/tx";
+        let tx_index: TxIndexConfig = psql.parse().unwrap();
+        assert!(matches!(&tx_index, TxIndexConfig::Psql(c) if c == "postgresql://localhost/tx"));
+        assert_eq!(tx_index.to_string(), psql);
+        assert!(matches!(
+            "PSQL:postgresql://localhost/tx".parse(),
+            Ok(TxIndexConfig::Psql(c)) if c == "postgresql://localhost/tx"
+        ));
+        assert!("psql".parse::<TxIndexConfig>().is_err());
+    }
+
+    #[test]
+    fn test_render_durations() {
+        let config = Config::default()
+            .p2p(P2PConfig::default().handshake_timeout(Duration::milliseconds(500)))
+            .consensus(ConsensusConfig::default().timeout_commit(Duration::milliseconds(1500)));
+
+        let m = config.into_model("/tmp").unwrap();
+        assert_eq!(m.p2p.handshake_timeout, "500ms");
+        assert_eq!(m.p2p.flush_throttle_timeout, "100ms");
+        assert_eq!(m.consensus.timeout_commit, "1.5s");
+        assert_eq!(m.statesync.trust_period, "168h0m0s");
+    }
+
+    #[test]
+    fn test_builtin_signer() {
+        let config = Config::default().builtin_signer(true);
+        assert_eq!(config.validate().is_ok(), crate::defined::BUILTIN_SIGNER);
+
+        if let Ok(m) = config.into_model("/tmp") {
+            assert_eq!(m.priv_validator_laddr, "unix:///tmp/sockets/privval");
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        use super::PeerAddress;
+
+        let peer: PeerAddress = "0123456789abcdef0123456789abcdef01234567@127.0.0.1:26656"
+            .parse()
+            .unwrap();
+
+        let config = Config::default()
+            .db_backend(DbBackend::RocksDB)
+            .p2p(P2PConfig::default().persistent_peers(vec![peer.clone()]))
+            .consensus(ConsensusConfig::default().timeout_commit(Duration::milliseconds(500)));
+
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["db_backend"], "rocksdb");
+        assert_eq!(json["consensus"]["timeout_commit"], "500ms");
+        assert_eq!(json["p2p"]["persistent_peers"][0], peer.to_string());
+
+        let parsed: Config = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.p2p.persistent_peers, vec![peer]);
+        assert_eq!(parsed.consensus.timeout_commit, Duration::milliseconds(500));
+
+        let partial: Config =
+            serde_json::from_str(r#"{"moniker": "node0", "log_level": "debug"}"#).unwrap();
+        assert_eq!(partial.moniker, "node0");
+        assert_eq!(partial.log_level.to_str(), "debug");
+        assert_eq!(partial.p2p.laddr, P2PConfig::default().laddr);
     }
 }
@@ -1,8 +1,13 @@
 use time::Duration;
 
-use super::define_build_mode_setter;
+use super::{define_build_mode_setter, PeerAddress};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct P2PConfig {
     /// Address to listen for incoming connections.
     pub laddr: String,
@@ -15,10 +20,10 @@ pub struct P2PConfig {
     pub external_address: String,
 
     /// List of seeds
-    pub seeds: Vec<String>,
+    pub seeds: Vec<PeerAddress>,
 
     /// List of persistent peers.
-    pub persistent_peers: Vec<String>,
+    pub persistent_peers: Vec<PeerAddress>,
 
     /// UPNP port forwarding
     pub upnp: bool,
@@ -36,9 +41,11 @@ pub struct P2PConfig {
     pub unconditional_peer_ids: Vec<String>,
 
     /// Maximum pause when redialing a persistent peer (if zero, exponential backoff is used)
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub persistent_peers_max_dial_period: Duration,
 
     /// Time to wait before flushing messages out on the connection
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub flush_throttle_timeout: Duration,
 
     /// Maximum size of a message packet payload, in bytes
@@ -66,7 +73,9 @@ pub struct P2PConfig {
     pub allow_duplicate_ip: bool,
 
     /// Peer connection configuration.
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub handshake_timeout: Duration,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub dial_timeout: Duration,
 }
 
@@ -102,9 +111,9 @@ impl P2PConfig {
 
     define_build_mode_setter!(external_address, str);
 
-    define_build_mode_setter!(seeds, Vec<String>);
+    define_build_mode_setter!(seeds, Vec<PeerAddress>);
 
-    define_build_mode_setter!(persistent_peers, Vec<String>);
+    define_build_mode_setter!(persistent_peers, Vec<PeerAddress>);
 
     define_build_mode_setter!(upnp, bool);
 
@@ -0,0 +1,91 @@
+use std::{fmt, str::FromStr};
+
+use crate::{Error, Result};
+
+use super::define_serde_by_str;
+
+/// Address of peer, format is `id@host:port`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerAddress {
+    /// Node ID, hex of node key's address
+    pub id: String,
+
+    /// IP or domain of peer
+    pub host: String,
+
+    /// P2P port of peer
+    pub port: u16,
+}
+
+impl PeerAddress {
+    pub fn new(id: &str, host: &str, port: u16) -> Self {
+        Self {
+            id: String::from(id),
+            host: String::from(host),
+            port,
+        }
+    }
+}
+
+impl fmt::Display for PeerAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}:{}", self.id, self.host, self.port)
+    }
+}
+
+impl FromStr for PeerAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let err = || Error::InvalidPeerAddress(String::from(s));
+
+        let addr = s.strip_prefix("tcp://").unwrap_or(s);
+
+        let (id, host_port) = addr.split_once('@').ok_or_else(err)?;
+        let (host, port) = host_port.rsplit_once(':').ok_or_else(err)?;
+
+        if id.len() != 40 || hex::decode(id).is_err() || host.is_empty() {
+            return Err(err());
+        }
+
+        let port = port.parse().map_err(|_| err())?;
+
+        Ok(Self {
+            id: id.to_lowercase(),
+            host: String::from(host),
+            port,
+        })
+    }
+}
+
+define_serde_by_str!(PeerAddress);
+
+#[cfg(test)]
+mod tests {
+    use rand::thread_rng;
+
+    use crate::{AlgorithmType, Keypair};
+
+    use super::PeerAddress;
+
+    #[test]
+    fn test_parse_and_format() {
+        let node_key = Keypair::generate(AlgorithmType::Ed25519, thread_rng());
+        let id = node_key.node_id();
+
+        let addr = PeerAddress::new(&id, "127.0.0.1", 26656);
+        let s = addr.to_string();
+        assert_eq!(s, format!("{}@127.0.0.1:26656", id));
+        assert_eq!(s.parse::<PeerAddress>().unwrap(), addr);
+
+        let s = format!("tcp://{}@[::1]:26656", id.to_uppercase());
+        let addr: PeerAddress = s.parse().unwrap();
+        assert_eq!(addr.id, id);
+        assert_eq!(addr.host, "[::1]");
+
+        assert!("127.0.0.1:26656".parse::<PeerAddress>().is_err());
+        assert!(format!("{}@127.0.0.1", id).parse::<PeerAddress>().is_err());
+        assert!(format!("{}@:26656", id).parse::<PeerAddress>().is_err());
+        assert!("abcd@127.0.0.1:26656".parse::<PeerAddress>().is_err());
+    }
+}
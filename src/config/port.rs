@@ -0,0 +1,236 @@
+use std::{
+    collections::BTreeSet,
+    fmt,
+    net::{Ipv4Addr, TcpListener},
+    str::FromStr,
+    sync::Mutex,
+};
+
+use super::{define_serde_by_str, ParseEnumError};
+use crate::{utils, Error};
+
+/// How to choose ports of P2P, RPC, pprof and Prometheus listen addresses
+///
+/// Parsed from and displayed as `fixed`, `ephemeral` or `range:<start>-<end>`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PortStrategy {
+    /// Use ports in addresses as is.
+    #[default]
+    Fixed,
+
+    /// Replace ports with free ones assigned by OS.
+    Ephemeral,
+
+    /// Replace ports with free ones in `start..=end`.
+    Range(u16, u16),
+}
+
+/// Ports chosen in this process, tendermint may not bind them yet, so they
+/// are skipped until released.
+static RESERVED_PORTS: Mutex<BTreeSet<u16>> = Mutex::new(BTreeSet::new());
+
+const PORT_STRATEGY_VALUES: &[&str] = &["fixed", "ephemeral", "range:<start>-<end>"];
+
+impl PortStrategy {
+    pub fn to_str(&self) -> &'static str {
+        match self {
+            PortStrategy::Fixed => "fixed",
+            PortStrategy::Ephemeral => "ephemeral",
+            PortStrategy::Range(_, _) => "range",
+        }
+    }
+
+    /// Rewrite port of `laddr` by strategy, ports in `used` are skipped and
+    /// the chosen one is pushed into it.
+    ///
+    /// Empty and unix socket addresses are returned as is.
+    pub(crate) fn apply(&self, laddr: String, used: &mut Vec<u16>) -> crate::Result<String> {
+        let (host, port) = match split_port(&laddr) {
+            Some(v) => v,
+            None => return Ok(laddr),
+        };
+
+        let port = self.choose(port, used)?;
+
+        Ok(format!("{}:{}", host, port))
+    }
+
+    /// Choose port by strategy instead of `port`, ports in `used` are skipped
+    /// and the chosen one is pushed into it.
+    ///
+    /// Ports chosen by `Ephemeral` and `Range` are also reserved in this
+    /// process until [`release_ports`].
+    pub(crate) fn choose(&self, port: u16, used: &mut Vec<u16>) -> crate::Result<u16> {
+        let port = match *self {
+            PortStrategy::Fixed => port,
+            PortStrategy::Ephemeral => loop {
+                let port = utils::free_port()?;
+
+                if !used.contains(&port) && reserve_port(port) {
+                    break port;
+                }
+            },
+            PortStrategy::Range(start, end) => {
+                let mut reserved = RESERVED_PORTS.lock().expect("lock poisoned");
+
+                let port = (start.max(1)..=end)
+                    .find(|p| !used.contains(p) && !reserved.contains(p) && is_port_free(*p))
+                    .ok_or_else(|| Error::NoFreePort(format!("{}-{}", start, end)))?;
+
+                reserved.insert(port);
+
+                port
+            }
+        };
+
+        used.push(port);
+
+        Ok(port)
+    }
+}
+
+/// Split `[scheme://]host:port`, `None` if no tcp port.
+pub(crate) fn split_port(laddr: &str) -> Option<(&str, u16)> {
+    if laddr.starts_with("unix://") {
+        return None;
+    }
+
+    let pos = laddr.rfind(':')?;
+    let port = laddr[pos + 1..].parse().ok()?;
+
+    Some((&laddr[..pos], port))
+}
+
+/// Reserve `port` in this process, false if already reserved.
+pub(crate) fn reserve_port(port: u16) -> bool {
+    RESERVED_PORTS.lock().expect("lock poisoned").insert(port)
+}
+
+/// Release reserved ports once their listeners are closed.
+pub(crate) fn release_ports(ports: impl IntoIterator<Item = u16>) {
+    let mut reserved = RESERVED_PORTS.lock().expect("lock poisoned");
+
+    for port in ports {
+        reserved.remove(&port);
+    }
+}
+
+fn is_port_free(port: u16) -> bool {
+    TcpListener::bind((Ipv4Addr::UNSPECIFIED, port)).is_ok()
+}
+
+impl fmt::Display for PortStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PortStrategy::Range(start, end) => write!(f, "range:{}-{}", start, end),
+            _ => f.write_str(self.to_str()),
+        }
+    }
+}
+
+impl FromStr for PortStrategy {
+    type Err = ParseEnumError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("fixed") {
+            return Ok(Self::Fixed);
+        }
+
+        if s.eq_ignore_ascii_case("ephemeral") {
+            return Ok(Self::Ephemeral);
+        }
+
+        let range = s.strip_prefix("range:").and_then(|r| {
+            let (start, end) = r.split_once('-')?;
+
+            Some((start.parse().ok()?, end.parse().ok()?))
+        });
+
+        match range {
+            Some((start, end)) if start != 0 && start <= end => Ok(Self::Range(start, end)),
+            _ => Err(ParseEnumError {
+                ty: "PortStrategy",
+                value: String::from(s),
+                expected: PORT_STRATEGY_VALUES,
+            }),
+        }
+    }
+}
+
+impl TryFrom<&str> for PortStrategy {
+    type Error = ParseEnumError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+define_serde_by_str!(PortStrategy);
+
+#[cfg(test)]
+mod tests {
+    use super::{release_ports, PortStrategy};
+
+    #[test]
+    fn test_apply() {
+        let mut used = Vec::new();
+
+        let laddr = PortStrategy::Fixed
+            .apply(String::from("tcp://0.0.0.0:26656"), &mut used)
+            .unwrap();
+        assert_eq!(laddr, "tcp://0.0.0.0:26656");
+
+        let unix = String::from("unix:///tmp/rpc.sock");
+        assert_eq!(
+            PortStrategy::Ephemeral
+                .apply(unix.clone(), &mut used)
+                .unwrap(),
+            unix
+        );
+        assert_eq!(
+            PortStrategy::Ephemeral
+                .apply(String::new(), &mut used)
+                .unwrap(),
+            ""
+        );
+
+        let laddr = PortStrategy::Ephemeral
+            .apply(String::from(":26660"), &mut used)
+            .unwrap();
+        assert!(laddr.starts_with(':'));
+        assert_ne!(laddr, ":26660");
+
+        let strategy: PortStrategy = "range:40100-40199".parse().unwrap();
+        assert_eq!(strategy, PortStrategy::Range(40100, 40199));
+        assert_eq!(strategy.to_string(), "range:40100-40199");
+
+        let a = strategy
+            .apply(String::from("tcp://127.0.0.1:26657"), &mut used)
+            .unwrap();
+        let b = strategy
+            .apply(String::from("tcp://127.0.0.1:26657"), &mut used)
+            .unwrap();
+        assert_ne!(a, b);
+        assert!(a.starts_with("tcp://127.0.0.1:401"));
+
+        assert!("range:2-1".parse::<PortStrategy>().is_err());
+        assert!("range:0-10".parse::<PortStrategy>().is_err());
+        assert!(PortStrategy::Range(1, 0)
+            .apply(String::from(":1"), &mut used)
+            .is_err());
+    }
+    #[test]
+    fn test_reserve_across_configs() {
+        let strategy = PortStrategy::Range(40200, 40299);
+
+        // Each config has its own `used`, like nodes started one by one.
+        let a = strategy.choose(26656, &mut Vec::new()).unwrap();
+        let b = strategy.choose(26656, &mut Vec::new()).unwrap();
+        assert_ne!(a, b);
+
+        release_ports([a]);
+        assert_eq!(strategy.choose(26656, &mut Vec::new()).unwrap(), a);
+
+        release_ports([a, b]);
+    }
+}
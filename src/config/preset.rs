@@ -0,0 +1,140 @@
+use time::Duration;
+
+use super::{
+    define_to_str_for_enum, Config, ConsensusConfig, FastSyncVersion, MempoolConfig, P2PConfig,
+    TxIndexConfig,
+};
+
+/// Role of node, to pick config preset
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NodeRole {
+    /// Validator behind sentries, never gossiped.
+    Validator,
+
+    /// Public node that shields a validator.
+    Sentry,
+
+    /// Crawls network and only serves peer addresses.
+    Seed,
+
+    /// Full node serving RPC.
+    #[default]
+    FullNode,
+
+    /// Full node keeps and indexes all history.
+    Archive,
+
+    /// Single local node producing blocks fast, for development.
+    LocalDev,
+}
+
+define_to_str_for_enum!(
+    NodeRole,
+    Validator => "validator",
+    Sentry => "sentry",
+    Seed => "seed",
+    FullNode => "full_node",
+    Archive => "archive",
+    LocalDev => "local_dev"
+);
+
+impl Config {
+    /// Config with settings suitable for `role`, other fields are default.
+    pub fn preset(role: NodeRole) -> Self {
+        let config = Self::default();
+
+        match role {
+            NodeRole::Validator => config
+                .p2p(P2PConfig::default().pex(false))
+                .consensus(ConsensusConfig::default().discard_abci_responses(true))
+                .tx_index(TxIndexConfig::Null),
+
+            NodeRole::Sentry => config
+                .p2p(
+                    P2PConfig::default()
+                        .pex(true)
+                        .max_num_inbound_peers(100)
+                        .max_num_outbound_peers(20),
+                )
+                .consensus(ConsensusConfig::default().discard_abci_responses(true))
+                .tx_index(TxIndexConfig::Null),
+
+            NodeRole::Seed => config
+                .p2p(
+                    P2PConfig::default()
+                        .pex(true)
+                        .seed_mode(true)
+                        .max_num_inbound_peers(1000)
+                        .max_num_outbound_peers(100),
+                )
+                .mempool(MempoolConfig::default().broadcast(false))
+                .consensus(ConsensusConfig::default().discard_abci_responses(true))
+                .tx_index(TxIndexConfig::Null),
+
+            NodeRole::FullNode => config
+                .consensus(ConsensusConfig::default().discard_abci_responses(false))
+                .tx_index(TxIndexConfig::Kv),
+
+            NodeRole::Archive => config
+                .enable_fast_sync(FastSyncVersion::default())
+                .consensus(ConsensusConfig::default().discard_abci_responses(false))
+                .tx_index(TxIndexConfig::Kv),
+
+            NodeRole::LocalDev => config
+                .p2p(
+                    P2PConfig::default()
+                        .pex(false)
+                        .local_net(true)
+                        .allow_duplicate_ip(true),
+                )
+                .consensus(
+                    ConsensusConfig::default()
+                        .timeout_commit(Duration::milliseconds(100))
+                        .skip_timeout_commit(true)
+                        .create_empty_blocks(false),
+                )
+                .tx_index(TxIndexConfig::Kv),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, NodeRole, TxIndexConfig};
+
+    #[test]
+    fn test_preset() {
+        let config = Config::preset(NodeRole::LocalDev);
+        assert!(config.consensus.skip_timeout_commit);
+        assert!(!config.consensus.create_empty_blocks);
+        assert!(config.p2p.local_net);
+
+        let config = Config::preset(NodeRole::Seed);
+        assert!(config.p2p.seed_mode);
+        assert!(!config.mempool.broadcast);
+        assert!(matches!(config.tx_index, TxIndexConfig::Null));
+
+        let config = Config::preset(NodeRole::Validator);
+        assert!(!config.p2p.pex);
+
+        let config = Config::preset(NodeRole::Sentry);
+        assert!(config.p2p.pex);
+        assert_eq!(config.p2p.max_num_inbound_peers, 100);
+        assert_eq!(config.p2p.max_num_outbound_peers, 20);
+        assert!(config.consensus.discard_abci_responses);
+        assert!(matches!(config.tx_index, TxIndexConfig::Null));
+
+        let config = Config::preset(NodeRole::FullNode);
+        assert!(!config.consensus.discard_abci_responses);
+        assert!(matches!(config.tx_index, TxIndexConfig::Kv));
+
+        let config = Config::preset(NodeRole::Archive);
+        assert!(config.state_sync.is_none());
+        assert!(config.fast_sync.is_some());
+        assert!(!config.consensus.discard_abci_responses);
+        assert!(matches!(config.tx_index, TxIndexConfig::Kv));
+
+        assert_eq!("full_node".parse::<NodeRole>().unwrap(), NodeRole::FullNode);
+        assert_eq!(NodeRole::all().count(), 6);
+    }
+}
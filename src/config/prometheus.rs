@@ -1,6 +1,11 @@
 use super::define_build_mode_setter;
 
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct PrometheusConfig {
     /// Address to listen for Prometheus collector(s) connections
     pub prometheus_listen_addr: String,
@@ -0,0 +1,133 @@
+use super::{define_build_mode_setter, port::split_port, P2PConfig, PeerAddress};
+
+/// Validator hidden behind sentry nodes
+///
+/// Validator only peers with its sentries and is never gossiped, sentries
+/// keep the validator private and always connected.
+#[derive(Debug, Clone)]
+pub struct SentryTopology {
+    /// Address of validator node
+    pub validator: PeerAddress,
+
+    /// Addresses of sentry nodes
+    pub sentries: Vec<PeerAddress>,
+
+    /// Other peers sentries connect to, like sentries of other validators.
+    pub public_peers: Vec<PeerAddress>,
+}
+
+impl SentryTopology {
+    pub fn new(validator: PeerAddress, sentries: Vec<PeerAddress>) -> Self {
+        Self {
+            validator,
+            sentries,
+            public_peers: Vec::new(),
+        }
+    }
+
+    define_build_mode_setter!(public_peers, Vec<PeerAddress>);
+
+    fn sentry_ids(&self) -> Vec<String> {
+        self.sentries.iter().map(|s| s.id.clone()).collect()
+    }
+
+    /// P2P config of validator based on `base`, listens on port of its address.
+    pub fn validator_p2p(&self, base: P2PConfig) -> P2PConfig {
+        let laddr = with_port(&base.laddr, self.validator.port);
+
+        base.laddr(&laddr)
+            .seeds(Vec::new())
+            .persistent_peers(self.sentries.clone())
+            .unconditional_peer_ids(self.sentry_ids())
+            .pex(false)
+    }
+
+    /// P2P config of sentry `i` based on `base`, listens on port of its
+    /// address. `None` if no sentry `i`.
+    pub fn sentry_p2p(&self, i: usize, base: P2PConfig) -> Option<P2PConfig> {
+        let sentry = self.sentries.get(i)?;
+
+        let persistent_peers = Some(self.validator.clone())
+            .into_iter()
+            .chain(self.sentries.iter().cloned())
+            .chain(self.public_peers.iter().cloned())
+            .filter(|p| p.id != sentry.id)
+            .collect();
+
+        let laddr = with_port(&base.laddr, sentry.port);
+
+        let config = base
+            .laddr(&laddr)
+            .persistent_peers(persistent_peers)
+            .private_peer_ids(vec![self.validator.id.clone()])
+            .unconditional_peer_ids(vec![self.validator.id.clone()])
+            .pex(true);
+
+        Some(config)
+    }
+
+    /// P2P configs of validator and all sentries.
+    pub fn p2p_configs(&self, base: P2PConfig) -> (P2PConfig, Vec<P2PConfig>) {
+        let sentries = (0..self.sentries.len())
+            .filter_map(|i| self.sentry_p2p(i, base.clone()))
+            .collect();
+
+        (self.validator_p2p(base), sentries)
+    }
+}
+
+/// Replace port of `laddr`, keep its host. Listen on all interfaces if
+/// `laddr` has no tcp port.
+fn with_port(laddr: &str, port: u16) -> String {
+    match split_port(laddr) {
+        Some((host, _)) => format!("{}:{}", host, port),
+        None => format!("tcp://0.0.0.0:{}", port),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{P2PConfig, PeerAddress, SentryTopology};
+
+    fn peer(i: u8, port: u16) -> PeerAddress {
+        PeerAddress::new(&hex::encode([i; 20]), "127.0.0.1", port)
+    }
+
+    #[test]
+    fn test_sentry_p2p() {
+        let topology = SentryTopology::new(peer(0, 26000), vec![peer(1, 26001), peer(2, 26002)])
+            .public_peers(vec![peer(3, 26003)]);
+
+        let (validator, sentries) = topology.p2p_configs(P2PConfig::default());
+
+        assert!(!validator.pex);
+        assert_eq!(validator.laddr, "tcp://0.0.0.0:26000");
+        assert_eq!(validator.persistent_peers, topology.sentries);
+        assert_eq!(
+            validator.unconditional_peer_ids,
+            vec![peer(1, 0).id, peer(2, 0).id]
+        );
+
+        let sentry = &sentries[0];
+        assert!(sentry.pex);
+        assert_eq!(sentry.laddr, "tcp://0.0.0.0:26001");
+        assert_eq!(
+            sentry.persistent_peers,
+            vec![peer(0, 26000), peer(2, 26002), peer(3, 26003)]
+        );
+        assert_eq!(sentry.private_peer_ids, vec![topology.validator.id.clone()]);
+        assert_eq!(sentry.unconditional_peer_ids, sentry.private_peer_ids);
+
+        assert!(topology.sentry_p2p(2, P2PConfig::default()).is_none());
+
+        let base = P2PConfig::default().laddr("tcp://127.0.0.1:1");
+        assert_eq!(
+            topology.validator_p2p(base.clone()).laddr,
+            "tcp://127.0.0.1:26000"
+        );
+        assert_eq!(
+            topology.sentry_p2p(1, base).unwrap().laddr,
+            "tcp://127.0.0.1:26002"
+        );
+    }
+}
@@ -1,8 +1,18 @@
 use time::Duration;
 
 use super::define_build_mode_setter;
+use crate::{defined, genesis::Genesis, rpc, utils::format_go_duration, Error, Result};
 
+/// Config of state sync
+///
+/// Only tendermint 0.34 and 0.37 support state sync, both have the same
+/// `[statesync]` keys. 0.33 has no state sync, so it is rejected there.
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct StateSyncConfig {
     /// RPC servers (comma-separated) for light client verification of the synced state machine and
     /// retrieval of state data for node bootstrapping. Also needs a trusted height and corresponding
@@ -13,16 +23,24 @@ pub struct StateSyncConfig {
     pub rpc_servers: Vec<String>,
     pub trust_height: u64,
     pub trust_hash: String,
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub trust_period: Duration,
 
     /// Time to spend discovering snapshots before initiating a restore.
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub discovery_time: Duration,
 
+    /// Temporary directory for state sync snapshot chunks, relative to home
+    /// if not absolute. Default is inside home, so it's removed by cleanup.
+    pub temp_dir: String,
+
     /// The timeout duration before re-requesting a chunk, possibly from a different
     /// peer (default: 1 minute).
+    #[cfg_attr(feature = "serde", serde(with = "crate::utils::serde_go_duration"))]
     pub chunk_request_timeout: Duration,
 
-    /// The number of concurrent chunk fetchers to run (default: 1).
+    /// The number of concurrent chunk fetchers to run, must be positive.
+    /// Tendermint reads it as int32.
     pub chunk_fetchers: u64,
 }
 
@@ -34,6 +52,7 @@ impl Default for StateSyncConfig {
             trust_hash: Default::default(),
             trust_period: Duration::hours(168),
             discovery_time: Duration::new(15, 0),
+            temp_dir: Default::default(),
             chunk_request_timeout: Duration::new(10, 0),
             chunk_fetchers: 4,
         }
@@ -51,7 +70,220 @@ impl StateSyncConfig {
 
     define_build_mode_setter!(discovery_time, Duration);
 
+    define_build_mode_setter!(temp_dir, str);
+
     define_build_mode_setter!(chunk_request_timeout, Duration);
 
     define_build_mode_setter!(chunk_fetchers, u64);
 }
+
+impl StateSyncConfig {
+    /// Check settings not related to genesis.
+    pub fn validate(&self) -> Result<()> {
+        if !defined::STATE_SYNC {
+            return Err(Error::InvalidConfig(String::from(
+                "state sync needs tendermint 0.34 or later",
+            )));
+        }
+
+        if self.chunk_fetchers == 0 || self.chunk_fetchers > i32::MAX as u64 {
+            return Err(Error::InvalidConfig(format!(
+                "state sync chunk_fetchers must be in 1..={}, got {}",
+                i32::MAX,
+                self.chunk_fetchers
+            )));
+        }
+
+        if !self.trust_period.is_positive() {
+            return Err(Error::InvalidConfig(String::from(
+                "state sync trust_period must be positive",
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Check `trust_period` doesn't exceed evidence max age of genesis,
+    /// otherwise misbehavior in trusted headers may be unpunishable.
+    pub fn validate_with_genesis<AppState>(&self, genesis: &Genesis<AppState>) -> Result<()> {
+        let max_age = genesis.consensus_params.evidence.max_age_duration;
+
+        if self.trust_period > max_age {
+            return Err(Error::InvalidConfig(format!(
+                "state sync trust_period {} exceeds evidence max_age_duration {}",
+                format_go_duration(self.trust_period),
+                format_go_duration(max_age)
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Fill `rpc_servers`, `trust_height` and `trust_hash` from RPC servers.
+    ///
+    /// Trusts header at `trust_offset` blocks below the lowest latest height
+    /// of all servers, its hash must be the same on every server. Tendermint
+    /// verifies light blocks against a witness, so at least two distinct
+    /// servers are needed. Servers are like `host:26657`, or
+    /// `Tendermint::rpc_laddr` of a local node.
+    pub fn bootstrap(self, rpc_servers: &[&str], trust_offset: u64) -> Result<Self> {
+        let mut distinct: Vec<String> = Vec::with_capacity(rpc_servers.len());
+        for server in rpc_servers {
+            if !distinct.iter().any(|s| s == server) {
+                distinct.push(String::from(*server));
+            }
+        }
+
+        if distinct.len() < 2 {
+            return Err(Error::InvalidConfig(String::from(
+                "state sync needs at least two distinct rpc servers",
+            )));
+        }
+
+        let rpc_servers = distinct;
+
+        let mut latest_height = u64::MAX;
+
+        for server in &rpc_servers {
+            let status = rpc::get(server, "/status")?;
+
+            let height = parse_height(&status["sync_info"]["latest_block_height"])
+                .ok_or_else(|| Error::RpcError(format!("no latest height from {}", server)))?;
+
+            latest_height = latest_height.min(height);
+        }
+
+        let trust_height = latest_height.saturating_sub(trust_offset).max(1);
+
+        let mut trust_hash: Option<String> = None;
+
+        for server in &rpc_servers {
+            let commit = rpc::get(server, &format!("/commit?height={}", trust_height))?;
+
+            let hash = commit["signed_header"]["commit"]["block_id"]["hash"]
+                .as_str()
+                .filter(|h| !h.is_empty())
+                .ok_or_else(|| {
+                    Error::RpcError(format!("no block hash at {} from {}", trust_height, server))
+                })?;
+
+            match &trust_hash {
+                Some(h) if !h.eq_ignore_ascii_case(hash) => {
+                    return Err(Error::TrustHashMismatch(format!(
+                        "{} has {} at height {}, others have {}",
+                        server, hash, trust_height, h
+                    )));
+                }
+                Some(_) => {}
+                None => trust_hash = Some(String::from(hash)),
+            }
+        }
+
+        Ok(Self {
+            rpc_servers,
+            trust_height,
+            trust_hash: trust_hash.unwrap_or_default(),
+            ..self
+        })
+    }
+}
+
+/// Height in RPC is string of integer.
+fn parse_height(v: &serde_json::Value) -> Option<u64> {
+    v.as_str().and_then(|h| h.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use time::Duration;
+
+    use super::StateSyncConfig;
+    use crate::{Error, Genesis, Keypair};
+
+    #[test]
+    fn test_validate() {
+        let config = StateSyncConfig::default();
+        let mut genesis = Genesis::<()>::generate(
+            Keypair::generate(crate::AlgorithmType::Ed25519, rand::thread_rng()).public_key,
+        );
+
+        if !crate::defined::STATE_SYNC {
+            assert!(config.validate().is_err());
+            return;
+        }
+
+        assert!(config.validate().is_ok());
+        assert!(config.clone().chunk_fetchers(0).validate().is_err());
+        assert!(config.validate_with_genesis(&genesis).is_ok());
+
+        genesis.consensus_params.evidence.max_age_duration = Duration::hours(24);
+        assert!(config.validate_with_genesis(&genesis).is_err());
+    }
+
+    /// RPC server with `latest` height, block hash is `hash` at every height.
+    fn serve(latest: u64, hash: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+
+                let mut line = String::new();
+                BufReader::new(&stream).read_line(&mut line).unwrap();
+
+                let result = if line.contains("/status") {
+                    format!(r#"{{"sync_info":{{"latest_block_height":"{}"}}}}"#, latest)
+                } else {
+                    format!(
+                        r#"{{"signed_header":{{"commit":{{"block_id":{{"hash":"{}"}}}}}}}}"#,
+                        hash
+                    )
+                };
+
+                let _ = write!(
+                    stream,
+                    "HTTP/1.0 200 OK\r\n\r\n{{\"jsonrpc\":\"2.0\",\"id\":-1,\"result\":{}}}",
+                    result
+                );
+            }
+        });
+
+        addr.to_string()
+    }
+
+    #[test]
+    fn test_bootstrap() {
+        let a = serve(120, "ABCD");
+        let b = serve(100, "ABCD");
+
+        let config = StateSyncConfig::default().bootstrap(&[&a, &b], 10).unwrap();
+        assert_eq!(config.trust_height, 90);
+        assert_eq!(config.trust_hash, "ABCD");
+        assert_eq!(config.rpc_servers, vec![a.clone(), b.clone()]);
+
+        let config = StateSyncConfig::default()
+            .bootstrap(&[&a, &b], 200)
+            .unwrap();
+        assert_eq!(config.trust_height, 1);
+
+        for servers in [&[][..], &[a.as_str()][..], &[a.as_str(), a.as_str()][..]] {
+            let err = StateSyncConfig::default()
+                .bootstrap(servers, 0)
+                .unwrap_err();
+            assert!(matches!(err, Error::InvalidConfig(_)));
+        }
+
+        let c = serve(120, "EF01");
+        let err = StateSyncConfig::default()
+            .bootstrap(&[&a, &c], 0)
+            .unwrap_err();
+        assert!(matches!(err, Error::TrustHashMismatch(_)));
+    }
+}
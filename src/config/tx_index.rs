@@ -1,3 +1,10 @@
+use std::{fmt, str::FromStr};
+
+use super::{define_serde_by_str, ParseEnumError};
+
+/// Indexer of transactions
+///
+/// Parsed from and displayed as `null`, `kv` or `psql:<conn>`.
 #[derive(Debug, Clone, Default)]
 pub enum TxIndexConfig {
     Null,
@@ -6,6 +13,8 @@ pub enum TxIndexConfig {
     Psql(String),
 }
 
+const TX_INDEX_VALUES: &[&str] = &["null", "kv", "psql:<conn>"];
+
 impl TxIndexConfig {
     pub fn to_str(&self) -> &'static str {
         match self {
@@ -14,4 +23,56 @@ impl TxIndexConfig {
             TxIndexConfig::Psql(_) => "psql",
         }
     }
+
+    /// All variants, connection string of psql is empty.
+    pub fn all() -> impl Iterator<Item = Self> {
+        [Self::Null, Self::Kv, Self::Psql(String::new())].into_iter()
+    }
 }
+
+impl fmt::Display for TxIndexConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TxIndexConfig::Psql(conn) => write!(f, "psql:{}", conn),
+            _ => f.write_str(self.to_str()),
+        }
+    }
+}
+
+impl FromStr for TxIndexConfig {
+    type Err = ParseEnumError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("null") {
+            return Ok(Self::Null);
+        }
+
+        if s.eq_ignore_ascii_case("kv") {
+            return Ok(Self::Kv);
+        }
+
+        if let Some(conn) = s
+            .get(..5)
+            .filter(|p| p.eq_ignore_ascii_case("psql:"))
+            .map(|_| &s[5..])
+        {
+            return Ok(Self::Psql(String::from(conn)));
+        }
+
+        Err(ParseEnumError {
+            ty: "TxIndexConfig",
+            value: String::from(s),
+            expected: TX_INDEX_VALUES,
+        })
+    }
+}
+
+impl TryFrom<&str> for TxIndexConfig {
+    type Error = ParseEnumError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+define_serde_by_str!(TxIndexConfig);
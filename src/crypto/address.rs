@@ -0,0 +1,287 @@
+use std::{fmt, str::FromStr};
+
+use crate::{Error, Result};
+
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const BECH32_GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+/// Max length of bech32 string of BIP-173.
+const BECH32_MAX_LEN: usize = 90;
+
+/// Address of account or validator, 20 bytes
+///
+/// Displayed as upper hex like tendermint, parsed from hex or bech32.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Address(pub [u8; 20]);
+
+impl AsRef<[u8]> for Address {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl From<[u8; 20]> for Address {
+    fn from(bytes: [u8; 20]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl Address {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let bytes = bytes.try_into().map_err(|_| {
+            Error::InvalidAddress(format!("address must be 20 bytes, got {}", bytes.len()))
+        })?;
+
+        Ok(Self(bytes))
+    }
+
+    /// Parse hex address, case insensitive.
+    pub fn from_hex(s: &str) -> Result<Self> {
+        Self::from_bytes(&hex::decode(s)?)
+    }
+
+    /// Upper hex, same as tendermint.
+    pub fn to_hex(&self) -> String {
+        hex::encode_upper(self.0)
+    }
+
+    /// Parse bech32 address, hrp must be `hrp`.
+    pub fn from_bech32(s: &str, hrp: &str) -> Result<Self> {
+        let (h, data) = bech32_decode(s)?;
+
+        if h != hrp {
+            return Err(Error::InvalidAddress(format!(
+                "expect hrp {}, got {}",
+                hrp, h
+            )));
+        }
+
+        Self::from_bytes(&data)
+    }
+
+    /// Bech32 with human readable part, like `cosmos` or `cosmosvalcons`.
+    pub fn to_bech32(&self, hrp: &str) -> Result<String> {
+        bech32_encode(hrp, &self.0)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+impl FromStr for Address {
+    type Err = Error;
+
+    /// Parse hex, or bech32 of any hrp.
+    fn from_str(s: &str) -> Result<Self> {
+        if s.len() == 40 {
+            if let Ok(address) = Self::from_hex(s) {
+                return Ok(address);
+            }
+        }
+
+        let (_, data) = bech32_decode(s)?;
+
+        Self::from_bytes(&data)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Address {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Address {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+fn bech32_polymod(values: impl Iterator<Item = u8>) -> u32 {
+    let mut chk = 1u32;
+
+    for v in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x1ffffff) << 5) ^ v as u32;
+
+        for (i, g) in BECH32_GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+
+    chk
+}
+
+fn bech32_hrp_expand(hrp: &str) -> impl Iterator<Item = u8> + '_ {
+    hrp.bytes()
+        .map(|b| b >> 5)
+        .chain(Some(0))
+        .chain(hrp.bytes().map(|b| b & 31))
+}
+
+/// Regroup bits, `pad` for encoding.
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Result<Vec<u8>> {
+    let mut acc = 0u32;
+    let mut bits = 0u32;
+    let mut ret = Vec::new();
+    let max = (1u32 << to) - 1;
+
+    for &v in data {
+        if (v as u32) >> from != 0 {
+            return Err(Error::InvalidAddress(String::from("invalid bech32 data")));
+        }
+
+        acc = (acc << from) | v as u32;
+        bits += from;
+
+        while bits >= to {
+            bits -= to;
+            ret.push(((acc >> bits) & max) as u8);
+        }
+    }
+
+    if pad {
+        if bits > 0 {
+            ret.push(((acc << (to - bits)) & max) as u8);
+        }
+    } else if bits >= from || (acc << (to - bits)) & max != 0 {
+        return Err(Error::InvalidAddress(String::from(
+            "invalid bech32 padding",
+        )));
+    }
+
+    Ok(ret)
+}
+
+/// Bech32 of BIP-173.
+pub(crate) fn bech32_encode(hrp: &str, data: &[u8]) -> Result<String> {
+    if hrp.is_empty()
+        || hrp
+            .bytes()
+            .any(|b| !(33..=126).contains(&b) || b.is_ascii_uppercase())
+    {
+        return Err(Error::InvalidAddress(format!("invalid hrp: {}", hrp)));
+    }
+
+    let data = convert_bits(data, 8, 5, true)?;
+
+    let polymod = bech32_polymod(
+        bech32_hrp_expand(hrp)
+            .chain(data.iter().copied())
+            .chain([0u8; 6]),
+    ) ^ 1;
+
+    let mut s = String::from(hrp);
+    s.push('1');
+
+    for v in data {
+        s.push(BECH32_CHARSET[v as usize] as char);
+    }
+
+    for i in 0..6 {
+        s.push(BECH32_CHARSET[((polymod >> (5 * (5 - i))) & 31) as usize] as char);
+    }
+
+    Ok(s)
+}
+
+/// Decode bech32 of BIP-173 to `(hrp, data)`.
+pub(crate) fn bech32_decode(s: &str) -> Result<(String, Vec<u8>)> {
+    if s.len() > BECH32_MAX_LEN {
+        return Err(Error::InvalidAddress(format!("bech32 too long: {}", s)));
+    }
+
+    if s.bytes().any(|b| b.is_ascii_lowercase()) && s.bytes().any(|b| b.is_ascii_uppercase()) {
+        return Err(Error::InvalidAddress(format!("bech32 mixed case: {}", s)));
+    }
+
+    let s = s.to_ascii_lowercase();
+
+    let pos = s
+        .rfind('1')
+        .filter(|&p| p > 0 && p + 7 <= s.len())
+        .ok_or_else(|| Error::InvalidAddress(format!("invalid bech32: {}", s)))?;
+
+    let (hrp, data) = (&s[..pos], &s[pos + 1..]);
+
+    if hrp.bytes().any(|b| !(33..=126).contains(&b)) {
+        return Err(Error::InvalidAddress(format!("invalid hrp: {}", hrp)));
+    }
+
+    let data = data
+        .bytes()
+        .map(|b| {
+            BECH32_CHARSET
+                .iter()
+                .position(|&c| c == b)
+                .map(|p| p as u8)
+                .ok_or_else(|| Error::InvalidAddress(format!("invalid bech32 character: {}", b)))
+        })
+        .collect::<Result<Vec<u8>>>()?;
+
+    if bech32_polymod(bech32_hrp_expand(hrp).chain(data.iter().copied())) != 1 {
+        return Err(Error::InvalidAddress(format!(
+            "invalid bech32 checksum: {}",
+            s
+        )));
+    }
+
+    let data = convert_bits(&data[..data.len() - 6], 5, 8, false)?;
+
+    Ok((String::from(hrp), data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bech32_decode, Address};
+
+    #[test]
+    fn test_bech32() {
+        // Valid checksums of BIP-173.
+        for s in [
+            "A12UEL5L",
+            "abcdef1qpzry9x8gf2tvdw0s3jn54khce6mua7lmqqqxw",
+            "split1checkupstagehandshakeupstreamerranterredcaperred2y9e3w",
+        ] {
+            assert!(bech32_decode(s).is_ok(), "{}", s);
+        }
+
+        for s in ["A1G7SGD8", "x1b4n0q5v", "li1dgmt3", "a12UEL5L"] {
+            assert!(bech32_decode(s).is_err(), "{}", s);
+        }
+
+        let address = Address::from_hex("28FF5C6D57D8CFD492B6FB42614536ED648E01FD").unwrap();
+        let bech32 = "cosmos19rl4cm2hmr8afy4kldpxz3fka4jguq0auqdal4";
+
+        assert_eq!(address.to_bech32("cosmos").unwrap(), bech32);
+        assert_eq!(Address::from_bech32(bech32, "cosmos").unwrap(), address);
+        assert!(Address::from_bech32(bech32, "cosmosvaloper").is_err());
+
+        assert_eq!(bech32.parse::<Address>().unwrap(), address);
+        assert_eq!(address.to_string().parse::<Address>().unwrap(), address);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let address = Address::from_hex("28FF5C6D57D8CFD492B6FB42614536ED648E01FD").unwrap();
+
+        let json = serde_json::to_string(&address).unwrap();
+        assert_eq!(json, "\"28FF5C6D57D8CFD492B6FB42614536ED648E01FD\"");
+        assert_eq!(serde_json::from_str::<Address>(&json).unwrap(), address);
+    }
+}
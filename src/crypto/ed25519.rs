@@ -1,6 +1,9 @@
 use curve25519_dalek_ng::{constants, scalar::Scalar};
 use rand_core::{CryptoRng, RngCore};
 use sha2::{Digest, Sha512};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::{Error, Result};
 
 use super::define_as_ref_u8_array;
 
@@ -8,17 +11,58 @@ use super::define_as_ref_u8_array;
 pub struct SecretKey(pub [u8; 64]);
 define_as_ref_u8_array!(SecretKey);
 
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PublicKey(pub [u8; 32]);
 define_as_ref_u8_array!(PublicKey);
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature(pub [u8; 64]);
+define_as_ref_u8_array!(Signature);
+
 impl SecretKey {
     pub fn generate(rng: impl RngCore + CryptoRng) -> Self {
         let mut rng = rng;
 
+        let mut seed = [0u8; 32];
+
+        rng.fill_bytes(&mut seed);
+
+        Self::from_seed(seed)
+    }
+
+    /// Parse `seed || public_key` bytes, public key must derive from seed.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != 64 {
+            return Err(Error::InvalidKey(format!(
+                "ed25519 secret key must be 64 bytes, got {}",
+                bytes.len()
+            )));
+        }
+
+        let mut seed = Zeroizing::new([0u8; 32]);
+        seed.copy_from_slice(&bytes[..32]);
+
+        let this = Self::from_seed(*seed);
+
+        if this.0[32..] != bytes[32..] {
+            return Err(Error::InvalidKey(String::from(
+                "ed25519 public key doesn't match secret key",
+            )));
+        }
+
+        Ok(this)
+    }
+
+    pub fn from_seed(seed: [u8; 32]) -> Self {
         let mut sk = [0u8; 64];
 
-        rng.fill_bytes(&mut sk[..32]);
+        sk[..32].copy_from_slice(&seed);
 
         // sk to pk
 
@@ -45,8 +89,13 @@ impl SecretKey {
             let pk = &mut sk[32..];
 
             pk.copy_from_slice(&compressed.0);
+
+            bits.zeroize();
         }
 
+        hash.zeroize();
+        digest.zeroize();
+
         Self(sk)
     }
 
@@ -56,4 +105,26 @@ impl SecretKey {
         pk.copy_from_slice(&self.0[32..]);
         PublicKey(pk)
     }
+
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        let mut seed = Zeroizing::new([0u8; 32]);
+        seed.copy_from_slice(&self.0[..32]);
+
+        let signing_key = Zeroizing::new(ed25519_consensus::SigningKey::from(*seed));
+
+        Signature(signing_key.sign(msg).to_bytes())
+    }
+}
+
+impl PublicKey {
+    pub fn verify(&self, msg: &[u8], signature: &Signature) -> Result<()> {
+        let verification_key = ed25519_consensus::VerificationKey::try_from(self.0)
+            .map_err(|_| Error::InvalidSignature)?;
+
+        let signature = ed25519_consensus::Signature::from(signature.0);
+
+        verification_key
+            .verify(&signature, msg)
+            .map_err(|_| Error::InvalidSignature)
+    }
 }
@@ -1,10 +1,17 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::Path,
+};
+
 use rand_core::{CryptoRng, RngCore};
 use ripemd::Ripemd160;
 use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
 
-use crate::model;
+use crate::{model, utils, Error, Result};
 
-use super::{ed25519, secp256k1, sr25519};
+use super::{address, ed25519, mnemonic, secp256k1, sr25519, Address};
 
 /// Secret key for tendermint
 #[derive(Debug, Clone)]
@@ -15,16 +22,36 @@ pub enum SecretKey {
 }
 
 impl SecretKey {
-    pub(crate) fn into_model(self) -> model::Key {
+    pub(crate) fn into_model(self) -> model::PrivKey {
         let (ty, value) = match self {
             Self::Ed25519(k) => ("tendermint/PrivKeyEd25519", base64::encode(k)),
             Self::Secp256k1(k) => ("tendermint/PrivKeySecp256k1", base64::encode(k)),
             Self::Sr25519(k) => ("tendermint/PrivKeySr25519", base64::encode(k)),
         };
 
-        model::Key {
+        model::PrivKey(model::Key {
             ty: String::from(ty),
             value,
+        })
+    }
+
+    pub(crate) fn from_model(m: &model::PrivKey) -> Result<Self> {
+        let bytes = Zeroizing::new(base64::decode(&m.value)?);
+
+        match m.ty.as_str() {
+            "tendermint/PrivKeyEd25519" => {
+                Ok(Self::Ed25519(ed25519::SecretKey::from_bytes(&bytes)?))
+            }
+            "tendermint/PrivKeySecp256k1" => {
+                Ok(Self::Secp256k1(secp256k1::SecretKey::from_bytes(&bytes)?))
+            }
+            "tendermint/PrivKeySr25519" => {
+                Ok(Self::Sr25519(sr25519::SecretKey::from_bytes(&bytes)?))
+            }
+            ty => Err(Error::InvalidKey(format!(
+                "unknown private key type: {}",
+                ty
+            ))),
         }
     }
 
@@ -36,6 +63,36 @@ impl SecretKey {
         }
     }
 
+    pub fn from_seed(ty: AlgorithmType, seed: &[u8; 32]) -> Result<Self> {
+        Ok(match ty {
+            AlgorithmType::Ed25519 => Self::Ed25519(ed25519::SecretKey::from_seed(*seed)),
+            AlgorithmType::Secp256k1 => Self::Secp256k1(secp256k1::SecretKey::from_bytes(seed)?),
+            AlgorithmType::Sr25519 => Self::Sr25519(sr25519::SecretKey::from_seed(*seed)),
+        })
+    }
+
+    /// Derive secret key from BIP-39 mnemonic.
+    ///
+    /// secp256k1 uses BIP-32 path like `m/44'/118'/0'/0/0`, ed25519 uses
+    /// SLIP-10 path which must be all hardened like `m/44'/118'/0'/0'/0'`.
+    /// sr25519 isn't supported.
+    pub fn from_mnemonic(
+        ty: AlgorithmType,
+        phrase: &str,
+        passphrase: &str,
+        path: &str,
+    ) -> Result<Self> {
+        let seed = mnemonic::mnemonic_to_seed(phrase, passphrase)?;
+
+        let seed = match ty {
+            AlgorithmType::Ed25519 => mnemonic::derive_ed25519(&seed, path)?,
+            AlgorithmType::Secp256k1 => mnemonic::derive_secp256k1(&seed, path)?,
+            AlgorithmType::Sr25519 => return Err(Error::UnsupportedAlgorithmType(ty)),
+        };
+
+        Self::from_seed(ty, &seed)
+    }
+
     pub fn public_key(&self) -> PublicKey {
         match self {
             Self::Ed25519(k) => PublicKey::Ed25519(k.public_key()),
@@ -43,6 +100,14 @@ impl SecretKey {
             Self::Sr25519(k) => PublicKey::Sr25519(k.public_key()),
         }
     }
+
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        match self {
+            Self::Ed25519(k) => Signature::Ed25519(k.sign(msg)),
+            Self::Secp256k1(k) => Signature::Secp256k1(k.sign(msg)),
+            Self::Sr25519(k) => Signature::Sr25519(k.sign(msg)),
+        }
+    }
 }
 
 /// Public key for tendermint
@@ -53,6 +118,16 @@ pub enum PublicKey {
     Sr25519(sr25519::PublicKey),
 }
 
+impl AsRef<[u8]> for PublicKey {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::Ed25519(k) => k.as_ref(),
+            Self::Secp256k1(k) => k.as_ref(),
+            Self::Sr25519(k) => k.as_ref(),
+        }
+    }
+}
+
 impl PublicKey {
     pub(crate) fn into_model(self) -> model::Key {
         let (ty, value) = match self {
@@ -67,7 +142,92 @@ impl PublicKey {
         }
     }
 
-    pub fn address(&self) -> [u8; 20] {
+    pub(crate) fn from_model(m: &model::Key) -> Result<Self> {
+        let bytes = base64::decode(&m.value)?;
+
+        let ty = match m.ty.as_str() {
+            "tendermint/PubKeyEd25519" => AlgorithmType::Ed25519,
+            "tendermint/PubKeySecp256k1" => AlgorithmType::Secp256k1,
+            "tendermint/PubKeySr25519" => AlgorithmType::Sr25519,
+            ty => {
+                return Err(Error::InvalidKey(format!(
+                    "unknown public key type: {}",
+                    ty
+                )))
+            }
+        };
+
+        Self::from_bytes(ty, &bytes)
+    }
+
+    pub fn algorithm_type(&self) -> AlgorithmType {
+        match self {
+            Self::Ed25519(_) => AlgorithmType::Ed25519,
+            Self::Secp256k1(_) => AlgorithmType::Secp256k1,
+            Self::Sr25519(_) => AlgorithmType::Sr25519,
+        }
+    }
+
+    pub fn verify(&self, msg: &[u8], signature: &Signature) -> Result<()> {
+        match (self, signature) {
+            (Self::Ed25519(k), Signature::Ed25519(s)) => k.verify(msg, s),
+            (Self::Secp256k1(k), Signature::Secp256k1(s)) => k.verify(msg, s),
+            (Self::Sr25519(k), Signature::Sr25519(s)) => k.verify(msg, s),
+            _ => Err(Error::InvalidSignature),
+        }
+    }
+
+    /// Parse raw public key bytes.
+    pub fn from_bytes(ty: AlgorithmType, bytes: &[u8]) -> Result<Self> {
+        let invalid = || {
+            Error::InvalidKey(format!(
+                "{:?} public key can't be {} bytes",
+                ty,
+                bytes.len()
+            ))
+        };
+
+        Ok(match ty {
+            AlgorithmType::Ed25519 => {
+                Self::Ed25519(ed25519::PublicKey(bytes.try_into().map_err(|_| invalid())?))
+            }
+            AlgorithmType::Secp256k1 => Self::Secp256k1(secp256k1::PublicKey::from_bytes(bytes)?),
+            AlgorithmType::Sr25519 => {
+                Self::Sr25519(sr25519::PublicKey(bytes.try_into().map_err(|_| invalid())?))
+            }
+        })
+    }
+
+    /// Bech32 of amino encoded public key, like `cosmosvalconspub1zcjduepq...`.
+    pub fn to_bech32(&self, hrp: &str) -> Result<String> {
+        let mut data = amino_prefix(self.algorithm_type()).to_vec();
+        data.extend_from_slice(self.as_ref());
+
+        address::bech32_encode(hrp, &data)
+    }
+
+    /// Parse bech32 of amino encoded public key, hrp must be `hrp`.
+    pub fn from_bech32(s: &str, hrp: &str) -> Result<Self> {
+        let (h, data) = address::bech32_decode(s)?;
+
+        if h != hrp {
+            return Err(Error::InvalidKey(format!("expect hrp {}, got {}", hrp, h)));
+        }
+
+        for ty in [
+            AlgorithmType::Ed25519,
+            AlgorithmType::Secp256k1,
+            AlgorithmType::Sr25519,
+        ] {
+            if let Some(key) = data.strip_prefix(amino_prefix(ty)) {
+                return Self::from_bytes(ty, key);
+            }
+        }
+
+        Err(Error::InvalidKey(String::from("unknown amino prefix")))
+    }
+
+    pub fn address(&self) -> Address {
         let mut addr = [0u8; 20];
 
         match self {
@@ -90,7 +250,55 @@ impl PublicKey {
             }
         }
 
-        addr
+        Address(addr)
+    }
+}
+
+/// Amino prefix and length of public key.
+fn amino_prefix(ty: AlgorithmType) -> &'static [u8] {
+    match ty {
+        AlgorithmType::Ed25519 => &[0x16, 0x24, 0xde, 0x64, 0x20],
+        AlgorithmType::Secp256k1 => &[0xeb, 0x5a, 0xe9, 0x87, 0x21],
+        AlgorithmType::Sr25519 => &[0x0d, 0xfb, 0x10, 0x05, 0x20],
+    }
+}
+
+/// Signature for tendermint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Signature {
+    Ed25519(ed25519::Signature),
+    Secp256k1(secp256k1::Signature),
+    Sr25519(sr25519::Signature),
+}
+
+impl AsRef<[u8]> for Signature {
+    fn as_ref(&self) -> &[u8] {
+        match self {
+            Self::Ed25519(s) => s.as_ref(),
+            Self::Secp256k1(s) => s.as_ref(),
+            Self::Sr25519(s) => s.as_ref(),
+        }
+    }
+}
+
+impl Signature {
+    /// Parse signature from raw bytes, all algorithms use 64 bytes signature.
+    pub fn from_bytes(ty: AlgorithmType, bytes: &[u8]) -> Result<Self> {
+        let bytes: [u8; 64] = bytes.try_into().map_err(|_| Error::InvalidSignature)?;
+
+        Ok(match ty {
+            AlgorithmType::Ed25519 => Self::Ed25519(ed25519::Signature(bytes)),
+            AlgorithmType::Secp256k1 => Self::Secp256k1(secp256k1::Signature(bytes)),
+            AlgorithmType::Sr25519 => Self::Sr25519(sr25519::Signature(bytes)),
+        })
+    }
+
+    pub fn algorithm_type(&self) -> AlgorithmType {
+        match self {
+            Self::Ed25519(_) => AlgorithmType::Ed25519,
+            Self::Secp256k1(_) => AlgorithmType::Secp256k1,
+            Self::Sr25519(_) => AlgorithmType::Sr25519,
+        }
     }
 }
 
@@ -101,6 +309,7 @@ pub struct Keypair {
     pub public_key: PublicKey,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AlgorithmType {
     Secp256k1,
     Ed25519,
@@ -115,13 +324,104 @@ impl Keypair {
         let priv_key = self.secret_key.into_model();
 
         model::Keypair {
-            address: hex::encode(address),
+            address: Some(hex::encode(address)),
             priv_key,
-            pub_key,
+            pub_key: Some(pub_key),
         }
     }
+
+    pub(crate) fn from_model(m: &model::Keypair) -> Result<Self> {
+        let secret_key = SecretKey::from_model(&m.priv_key)?;
+        let public_key = secret_key.public_key();
+
+        if let Some(pub_key) = &m.pub_key {
+            let pub_key = PublicKey::from_model(pub_key)?;
+
+            if pub_key.algorithm_type() != public_key.algorithm_type()
+                || pub_key.as_ref() != public_key.as_ref()
+            {
+                return Err(Error::InvalidKey(String::from(
+                    "pub_key doesn't match priv_key",
+                )));
+            }
+        }
+
+        if let Some(address) = &m.address {
+            if !address.eq_ignore_ascii_case(&hex::encode(public_key.address())) {
+                return Err(Error::InvalidKey(String::from(
+                    "address doesn't match priv_key",
+                )));
+            }
+        }
+
+        Ok(Self {
+            secret_key,
+            public_key,
+        })
+    }
+
+    /// Parse `priv_validator_key.json` or `node_key.json` of tendermint.
+    pub fn from_tendermint_json(json: &str) -> Result<Self> {
+        let m: model::Keypair = serde_json::from_str(json)?;
+
+        Self::from_model(&m)
+    }
+
+    /// Load keypair from `priv_validator_key.json` or `node_key.json` file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let mut json = Zeroizing::new(String::new());
+        File::open(path)?.read_to_string(&mut json)?;
+
+        Self::from_tendermint_json(&json)
+    }
+
+    /// Save keypair in tendermint's key file format.
+    ///
+    /// File is only readable and writable by owner.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut file = utils::create_private_file(path)?;
+        let m = self.clone().into_model();
+        let cs = Zeroizing::new(serde_json::to_string_pretty(&m)?);
+        file.write_all(cs.as_bytes())?;
+
+        Ok(())
+    }
+
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        self.secret_key.sign(msg)
+    }
+
+    /// Node ID used by p2p, hex of address.
+    pub fn node_id(&self) -> String {
+        hex::encode(self.public_key.address())
+    }
+
     pub fn generate(ty: AlgorithmType, rng: impl RngCore + CryptoRng) -> Self {
         let secret_key = SecretKey::generate(ty, rng);
+
+        Self::from_secret_key(secret_key)
+    }
+
+    /// Deterministic keypair from 32 bytes seed, useful for test fixtures.
+    pub fn from_seed(ty: AlgorithmType, seed: &[u8; 32]) -> Result<Self> {
+        let secret_key = SecretKey::from_seed(ty, seed)?;
+
+        Ok(Self::from_secret_key(secret_key))
+    }
+
+    /// Recover keypair from BIP-39 mnemonic, see [`SecretKey::from_mnemonic`].
+    pub fn from_mnemonic(
+        ty: AlgorithmType,
+        phrase: &str,
+        passphrase: &str,
+        path: &str,
+    ) -> Result<Self> {
+        let secret_key = SecretKey::from_mnemonic(ty, phrase, passphrase, path)?;
+
+        Ok(Self::from_secret_key(secret_key))
+    }
+
+    pub fn from_secret_key(secret_key: SecretKey) -> Self {
         let public_key = secret_key.public_key();
 
         Self {
@@ -130,3 +430,222 @@ impl Keypair {
         }
     }
 }
+
+/// Serde in tendermint's `{type, value}` json form.
+#[cfg(feature = "serde")]
+macro_rules! define_serde_by_model {
+    ($t:ty, $m:ty) => {
+        impl serde::Serialize for $t {
+            fn serialize<S: serde::Serializer>(
+                &self,
+                serializer: S,
+            ) -> std::result::Result<S::Ok, S::Error> {
+                serde::Serialize::serialize(&self.clone().into_model(), serializer)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $t {
+            fn deserialize<D: serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<Self, D::Error> {
+                let m = <$m as serde::Deserialize>::deserialize(deserializer)?;
+
+                Self::from_model(&m).map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+#[cfg(feature = "serde")]
+define_serde_by_model!(SecretKey, model::PrivKey);
+
+#[cfg(feature = "serde")]
+define_serde_by_model!(PublicKey, model::Key);
+
+#[cfg(feature = "serde")]
+define_serde_by_model!(Keypair, model::Keypair);
+
+#[cfg(test)]
+mod tests {
+    use rand::thread_rng;
+
+    use tempfile::tempdir;
+
+    use crate::{model, Error};
+
+    use super::{sr25519, AlgorithmType, Keypair, PublicKey, SecretKey, Signature};
+
+    fn sign_and_verify(ty: AlgorithmType) {
+        let keypair = Keypair::generate(ty, thread_rng());
+
+        let msg = b"embedded tendermint";
+        let signature = keypair.sign(msg);
+
+        assert_eq!(signature.algorithm_type(), ty);
+        assert_eq!(signature.as_ref().len(), 64);
+
+        keypair.public_key.verify(msg, &signature).unwrap();
+        assert!(keypair.public_key.verify(b"other", &signature).is_err());
+
+        let parsed = Signature::from_bytes(ty, signature.as_ref()).unwrap();
+        assert_eq!(parsed, signature);
+
+        let other = Keypair::generate(ty, thread_rng());
+        assert!(other.public_key.verify(msg, &signature).is_err());
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        sign_and_verify(AlgorithmType::Ed25519);
+        sign_and_verify(AlgorithmType::Secp256k1);
+        sign_and_verify(AlgorithmType::Sr25519);
+    }
+
+    #[test]
+    fn test_public_key_bech32() {
+        for (ty, hrp, prefix) in [
+            (
+                AlgorithmType::Ed25519,
+                "cosmosvalconspub",
+                "cosmosvalconspub1zcjduepq",
+            ),
+            (AlgorithmType::Secp256k1, "cosmospub", "cosmospub1addwnpepq"),
+        ] {
+            let public_key = Keypair::generate(ty, thread_rng()).public_key;
+
+            let s = public_key.to_bech32(hrp).unwrap();
+            assert!(s.starts_with(prefix));
+
+            let parsed = PublicKey::from_bech32(&s, hrp).unwrap();
+            assert_eq!(parsed.as_ref(), public_key.as_ref());
+            assert!(PublicKey::from_bech32(&s, "cosmos").is_err());
+        }
+    }
+
+    #[test]
+    fn test_secp256k1_low_s() {
+        let keypair = Keypair::generate(AlgorithmType::Secp256k1, thread_rng());
+
+        for i in 0u8..16 {
+            let signature = keypair.sign(&[i]);
+
+            let signature = k256::ecdsa::Signature::try_from(signature.as_ref()).unwrap();
+            assert!(signature.normalize_s().is_none());
+        }
+    }
+
+    #[test]
+    fn test_sr25519_public_key() {
+        let secret_key = SecretKey::generate(AlgorithmType::Sr25519, thread_rng());
+
+        if let (SecretKey::Sr25519(sk), PublicKey::Sr25519(pk)) =
+            (&secret_key, secret_key.public_key())
+        {
+            let keypair = schnorrkel::MiniSecretKey::from_bytes(&sk.0)
+                .unwrap()
+                .expand_to_keypair(schnorrkel::ExpansionMode::Ed25519);
+
+            assert_eq!(keypair.public.to_bytes(), pk.0);
+        } else {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn test_sr25519_verify_known_answer() {
+        // Signed by mini secret key `[7u8; 32]` over "tendermint", using the
+        // empty signing context of tendermint's go-schnorrkel.
+        let public_key = PublicKey::from_bytes(
+            AlgorithmType::Sr25519,
+            &hex::decode("7c0f469d3bd340bae718203fa30ca071a5e37c751e891dbded837b213d45d91d")
+                .unwrap(),
+        )
+        .unwrap();
+
+        let signature = Signature::from_bytes(
+            AlgorithmType::Sr25519,
+            &hex::decode(
+                "ca1128432e38812649b591574d2de6a12ac3e822e2b7b62bef479fe60eba1d69\
+                 95364fea245176e577a901a6d45ea8e80e639980df921d82752d380a90861681",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        public_key.verify(b"tendermint", &signature).unwrap();
+        assert!(public_key.verify(b"substrate", &signature).is_err());
+
+        let secret_key = SecretKey::Sr25519(sr25519::SecretKey::from_seed([7u8; 32]));
+        assert_eq!(secret_key.public_key().as_ref(), public_key.as_ref());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempdir().unwrap();
+
+        for ty in [
+            AlgorithmType::Ed25519,
+            AlgorithmType::Secp256k1,
+            AlgorithmType::Sr25519,
+        ] {
+            let path = dir.path().join("priv_validator_key.json");
+
+            let keypair = Keypair::generate(ty, thread_rng());
+            keypair.save(&path).unwrap();
+
+            let loaded = Keypair::load(&path).unwrap();
+            assert_eq!(loaded.public_key.as_ref(), keypair.public_key.as_ref());
+            assert_eq!(loaded.public_key.algorithm_type(), ty);
+        }
+    }
+
+    #[test]
+    fn test_load_node_key() {
+        let keypair = Keypair::generate(AlgorithmType::Ed25519, thread_rng());
+        let m = keypair.clone().into_model();
+
+        let json = format!(
+            r#"{{"priv_key":{{"type":"{}","value":"{}"}}}}"#,
+            m.priv_key.ty, m.priv_key.value
+        );
+
+        let loaded = Keypair::from_tendermint_json(&json).unwrap();
+        assert_eq!(loaded.public_key.address(), keypair.public_key.address());
+    }
+
+    #[test]
+    fn test_load_mismatch() {
+        let keypair = Keypair::generate(AlgorithmType::Ed25519, thread_rng());
+        let other = Keypair::generate(AlgorithmType::Ed25519, thread_rng());
+
+        let mut m = keypair.clone().into_model();
+        m.address = Some(hex::encode_upper(keypair.public_key.address()));
+        let json = serde_json::to_string(&m).unwrap();
+        Keypair::from_tendermint_json(&json).unwrap();
+
+        let mut m = keypair.clone().into_model();
+        m.address = Some(hex::encode(other.public_key.address()));
+        let json = serde_json::to_string(&m).unwrap();
+        assert!(matches!(
+            Keypair::from_tendermint_json(&json),
+            Err(Error::InvalidKey(_))
+        ));
+
+        let mut m = keypair.into_model();
+        m.pub_key = other.into_model().pub_key;
+        let json = serde_json::to_string(&m).unwrap();
+        assert!(matches!(
+            Keypair::from_tendermint_json(&json),
+            Err(Error::InvalidKey(_))
+        ));
+
+        let m = model::PrivKey(model::Key {
+            ty: String::from("tendermint/PrivKeyUnknown"),
+            value: String::new(),
+        });
+        assert!(matches!(
+            SecretKey::from_model(&m),
+            Err(Error::InvalidKey(_))
+        ));
+    }
+}
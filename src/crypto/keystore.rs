@@ -0,0 +1,225 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    path::Path,
+};
+
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, KeyInit, Nonce};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroizing;
+
+use crate::{model, utils, Error, Result};
+
+use super::Keypair;
+
+const KEYSTORE_VERSION: u32 = 1;
+const KDF_SCRYPT: &str = "scrypt";
+const CIPHER_CHACHA20POLY1305: &str = "chacha20poly1305";
+
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+
+/// Caps of scrypt params read from file, to bound memory and time of kdf.
+const MAX_SCRYPT_LOG_N: u8 = 20;
+const MAX_SCRYPT_R: u32 = 32;
+const MAX_SCRYPT_P: u32 = 16;
+
+/// Keypair encrypted by passphrase
+///
+/// Passphrase is stretched by scrypt, then tendermint's key json is sealed
+/// by ChaCha20-Poly1305.
+#[derive(Debug, Clone)]
+pub struct EncryptedKeypair(model::Keystore);
+
+impl EncryptedKeypair {
+    pub fn from_json(json: &str) -> Result<Self> {
+        let m: model::Keystore = serde_json::from_str(json)?;
+
+        if m.version != KEYSTORE_VERSION {
+            return Err(Error::InvalidKeystore(format!(
+                "unsupported version: {}",
+                m.version
+            )));
+        }
+
+        if m.kdf != KDF_SCRYPT || m.cipher != CIPHER_CHACHA20POLY1305 {
+            return Err(Error::InvalidKeystore(format!(
+                "unsupported kdf or cipher: {}, {}",
+                m.kdf, m.cipher
+            )));
+        }
+
+        Ok(Self(m))
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(&self.0)?)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let mut json = String::new();
+        File::open(path)?.read_to_string(&mut json)?;
+
+        Self::from_json(&json)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut file = utils::create_private_file(path)?;
+        file.write_all(self.to_json()?.as_bytes())?;
+
+        Ok(())
+    }
+
+    pub fn decrypt(&self, passphrase: &str) -> Result<Keypair> {
+        let m = &self.0;
+
+        if m.kdf_params.log_n > MAX_SCRYPT_LOG_N
+            || m.kdf_params.r > MAX_SCRYPT_R
+            || m.kdf_params.p > MAX_SCRYPT_P
+        {
+            return Err(Error::InvalidKeystore(format!(
+                "scrypt params too large: log_n {}, r {}, p {}",
+                m.kdf_params.log_n, m.kdf_params.r, m.kdf_params.p
+            )));
+        }
+
+        let salt = hex::decode(&m.kdf_params.salt)?;
+        let key = derive_key(
+            passphrase,
+            &salt,
+            m.kdf_params.log_n,
+            m.kdf_params.r,
+            m.kdf_params.p,
+        )?;
+
+        let nonce = hex::decode(&m.nonce)?;
+        if nonce.len() != 12 {
+            return Err(Error::InvalidKeystore(String::from(
+                "nonce must be 12 bytes",
+            )));
+        }
+
+        let ciphertext = base64::decode(&m.ciphertext)?;
+
+        let cipher = ChaCha20Poly1305::new(key.as_ref().into());
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+                .map_err(|_| Error::KeystoreDecryptError)?,
+        );
+
+        let m: model::Keypair = serde_json::from_slice(&plaintext)?;
+
+        Keypair::from_model(&m)
+    }
+}
+
+impl Keypair {
+    /// Encrypt keypair by passphrase.
+    pub fn encrypt(
+        &self,
+        passphrase: &str,
+        rng: impl RngCore + CryptoRng,
+    ) -> Result<EncryptedKeypair> {
+        let mut rng = rng;
+
+        let mut salt = [0u8; 32];
+        rng.fill_bytes(&mut salt);
+
+        let mut nonce = [0u8; 12];
+        rng.fill_bytes(&mut nonce);
+
+        let key = derive_key(passphrase, &salt, SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P)?;
+
+        let plaintext = Zeroizing::new(serde_json::to_vec(&self.clone().into_model())?);
+
+        let cipher = ChaCha20Poly1305::new(key.as_ref().into());
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext.as_ref())
+            .map_err(|_| Error::InvalidKeystore(String::from("encrypt failed")))?;
+
+        Ok(EncryptedKeypair(model::Keystore {
+            version: KEYSTORE_VERSION,
+            kdf: String::from(KDF_SCRYPT),
+            kdf_params: model::ScryptParams {
+                log_n: SCRYPT_LOG_N,
+                r: SCRYPT_R,
+                p: SCRYPT_P,
+                salt: hex::encode(salt),
+            },
+            cipher: String::from(CIPHER_CHACHA20POLY1305),
+            nonce: hex::encode(nonce),
+            ciphertext: base64::encode(ciphertext),
+        }))
+    }
+
+    /// Decrypt keypair by passphrase.
+    pub fn decrypt(encrypted: &EncryptedKeypair, passphrase: &str) -> Result<Self> {
+        encrypted.decrypt(passphrase)
+    }
+}
+
+fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    log_n: u8,
+    r: u32,
+    p: u32,
+) -> Result<Zeroizing<[u8; 32]>> {
+    let params = scrypt::Params::new(log_n, r, p)
+        .map_err(|_| Error::InvalidKeystore(String::from("invalid scrypt params")))?;
+
+    let mut key = Zeroizing::new([0u8; 32]);
+
+    scrypt::scrypt(passphrase.as_bytes(), salt, &params, key.as_mut())
+        .map_err(|_| Error::InvalidKeystore(String::from("invalid scrypt output length")))?;
+
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::thread_rng;
+    use tempfile::tempdir;
+
+    use crate::{AlgorithmType, Error, Keypair};
+
+    use super::EncryptedKeypair;
+
+    #[test]
+    fn test_encrypt_and_decrypt() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("keystore.json");
+
+        let keypair = Keypair::generate(AlgorithmType::Secp256k1, thread_rng());
+
+        let encrypted = keypair.encrypt("passphrase", thread_rng()).unwrap();
+        encrypted.save(&path).unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = path.metadata().unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        let loaded = EncryptedKeypair::load(&path).unwrap();
+
+        let decrypted = Keypair::decrypt(&loaded, "passphrase").unwrap();
+        assert_eq!(decrypted.public_key.as_ref(), keypair.public_key.as_ref());
+
+        assert!(matches!(
+            loaded.decrypt("wrong"),
+            Err(Error::KeystoreDecryptError)
+        ));
+
+        let mut costly = loaded.clone();
+        costly.0.kdf_params.log_n = 30;
+        assert!(matches!(
+            costly.decrypt("passphrase"),
+            Err(Error::InvalidKeystore(_))
+        ));
+    }
+}
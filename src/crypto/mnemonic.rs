@@ -0,0 +1,164 @@
+//! BIP-39 mnemonic and hierarchical deterministic derivation
+//!
+//! secp256k1 keys use BIP-32, ed25519 keys use SLIP-10 which only supports
+//! hardened path.
+
+use bip32::{DerivationPath, XPrv};
+use bip39::Mnemonic;
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+
+use crate::{Error, Result};
+
+type HmacSha512 = Hmac<Sha512>;
+
+const SLIP10_ED25519_SEED: &[u8] = b"ed25519 seed";
+
+/// Convert BIP-39 english mnemonic to 64 bytes seed.
+pub(crate) fn mnemonic_to_seed(phrase: &str, passphrase: &str) -> Result<[u8; 64]> {
+    let mnemonic = Mnemonic::parse(phrase)?;
+
+    Ok(mnemonic.to_seed(passphrase))
+}
+
+/// Derive secp256k1 secret key by BIP-32.
+pub(crate) fn derive_secp256k1(seed: &[u8], path: &str) -> Result<[u8; 32]> {
+    let path: DerivationPath = path.parse()?;
+
+    let xprv = XPrv::derive_from_path(seed, &path)?;
+
+    Ok(xprv.private_key().to_bytes().into())
+}
+
+/// Derive ed25519 seed by SLIP-10.
+pub(crate) fn derive_ed25519(seed: &[u8], path: &str) -> Result<[u8; 32]> {
+    let path: DerivationPath = path.parse()?;
+
+    let (mut key, mut chain_code) = hmac_sha512(SLIP10_ED25519_SEED, &[seed]);
+
+    for child in path.iter() {
+        if !child.is_hardened() {
+            return Err(Error::InvalidKey(format!(
+                "ed25519 only supports hardened derivation, got {}",
+                child
+            )));
+        }
+
+        (key, chain_code) = hmac_sha512(&chain_code, &[&[0], &key, &child.to_bytes()]);
+    }
+
+    Ok(key)
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> ([u8; 32], [u8; 32]) {
+    let mut hmac = HmacSha512::new_from_slice(key).expect("hmac accepts any key length");
+
+    for d in data {
+        hmac.update(d);
+    }
+
+    let result = hmac.finalize().into_bytes();
+
+    let mut left = [0u8; 32];
+    let mut right = [0u8; 32];
+
+    left.copy_from_slice(&result[..32]);
+    right.copy_from_slice(&result[32..]);
+
+    (left, right)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AlgorithmType, Keypair};
+
+    use super::{derive_ed25519, derive_secp256k1, mnemonic_to_seed};
+
+    const SEED: &str = "000102030405060708090a0b0c0d0e0f";
+
+    const MNEMONIC: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_bip39_seed() {
+        let seed = mnemonic_to_seed(MNEMONIC, "TREZOR").unwrap();
+
+        assert_eq!(
+            hex::encode(seed),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+
+        assert!(mnemonic_to_seed("abandon abandon", "").is_err());
+    }
+
+    #[test]
+    fn test_bip32_secp256k1() {
+        let seed = hex::decode(SEED).unwrap();
+
+        let vectors = [
+            (
+                "m",
+                "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35",
+            ),
+            (
+                "m/0'",
+                "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea",
+            ),
+            (
+                "m/0'/1/2'/2/1000000000",
+                "471b76e389e528d6de6d816857e012c5455051cad6660850e58372a6c3e6e7c8",
+            ),
+        ];
+
+        for (path, key) in vectors {
+            assert_eq!(hex::encode(derive_secp256k1(&seed, path).unwrap()), key);
+        }
+    }
+
+    #[test]
+    fn test_slip10_ed25519() {
+        let seed = hex::decode(SEED).unwrap();
+
+        let vectors = [
+            (
+                "m",
+                "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7",
+                "a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed",
+            ),
+            (
+                "m/0'",
+                "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3",
+                "8c8a13df77a28f3445213a0f432fde644acaa215fc72dcdf300d5efaa85d350c",
+            ),
+            (
+                "m/0'/1'/2'/2'/1000000000'",
+                "8f94d394a8e8fd6b1bc2f3f49f5c47e385281d5c17e65324b0f62483e37e8793",
+                "3c24da049451555d51a7014a37337aa4e12d41e485abccfa46b47dfb2af54b7a",
+            ),
+        ];
+
+        for (path, expect, public_key) in vectors {
+            let key = derive_ed25519(&seed, path).unwrap();
+            assert_eq!(hex::encode(key), expect);
+
+            let keypair = Keypair::from_seed(AlgorithmType::Ed25519, &key).unwrap();
+            assert_eq!(hex::encode(keypair.public_key), public_key);
+        }
+
+        assert!(derive_ed25519(&seed, "m/0").is_err());
+    }
+
+    #[test]
+    fn test_cosmos_address() {
+        let keypair =
+            Keypair::from_mnemonic(AlgorithmType::Secp256k1, MNEMONIC, "", "m/44'/118'/0'/0/0")
+                .unwrap();
+
+        // cosmos19rl4cm2hmr8afy4kldpxz3fka4jguq0auqdal4
+        assert_eq!(
+            hex::encode(keypair.public_key.address()),
+            "28ff5c6d57d8cfd492b6fb42614536ed648e01fd"
+        );
+
+        assert!(Keypair::from_mnemonic(AlgorithmType::Sr25519, MNEMONIC, "", "m").is_err());
+    }
+}
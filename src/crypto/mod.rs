@@ -9,6 +9,14 @@ pub mod secp256k1;
 mod keypair;
 pub use keypair::*;
 
+mod address;
+pub use address::*;
+
+mod mnemonic;
+
+mod keystore;
+pub use keystore::*;
+
 macro_rules! define_as_ref_u8_array {
     ($t:ty) => {
         impl AsRef<[u8]> for $t {
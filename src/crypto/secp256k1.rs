@@ -1,5 +1,11 @@
-use k256::ecdsa;
+use k256::ecdsa::{
+    self,
+    signature::{Signer, Verifier},
+};
 use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+use crate::{Error, Result};
 
 use super::define_as_ref_u8_array;
 
@@ -7,10 +13,21 @@ use super::define_as_ref_u8_array;
 pub struct SecretKey(pub [u8; 32], ecdsa::SigningKey);
 define_as_ref_u8_array!(SecretKey);
 
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PublicKey(pub [u8; 33], ecdsa::VerifyingKey);
 define_as_ref_u8_array!(PublicKey);
 
+/// Compact `r || s` signature with low-S, over SHA-256 of message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature(pub [u8; 64]);
+define_as_ref_u8_array!(Signature);
+
 impl SecretKey {
     pub fn generate(rng: impl RngCore + CryptoRng) -> Self {
         let secret_key = ecdsa::SigningKey::random(rng);
@@ -19,10 +36,53 @@ impl SecretKey {
         Self(bytes, secret_key)
     }
 
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let secret_key = ecdsa::SigningKey::from_bytes(bytes)
+            .map_err(|_| Error::InvalidKey(String::from("invalid secp256k1 secret key")))?;
+        let bytes = secret_key.to_bytes().into();
+
+        Ok(Self(bytes, secret_key))
+    }
+
     pub fn public_key(&self) -> PublicKey {
         let public_key = self.1.verifying_key();
         let bytes = public_key.to_bytes().into();
 
         PublicKey(bytes, public_key)
     }
+
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        let signature: ecdsa::Signature = self.1.sign(msg);
+        let signature = signature.normalize_s().unwrap_or(signature);
+
+        let mut bytes = [0u8; 64];
+        bytes.copy_from_slice(signature.as_ref());
+
+        Signature(bytes)
+    }
+}
+
+impl PublicKey {
+    /// Parse compressed SEC1 bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let public_key = ecdsa::VerifyingKey::from_sec1_bytes(bytes)
+            .map_err(|_| Error::InvalidKey(String::from("invalid secp256k1 public key")))?;
+        let bytes = public_key.to_bytes().into();
+
+        Ok(Self(bytes, public_key))
+    }
+
+    pub fn verify(&self, msg: &[u8], signature: &Signature) -> Result<()> {
+        let signature = ecdsa::Signature::try_from(signature.0.as_ref())
+            .map_err(|_| Error::InvalidSignature)?;
+
+        // Tendermint only accept low-S signature.
+        if signature.normalize_s().is_some() {
+            return Err(Error::InvalidSignature);
+        }
+
+        self.1
+            .verify(msg, &signature)
+            .map_err(|_| Error::InvalidSignature)
+    }
 }
@@ -1,17 +1,34 @@
 use curve25519_dalek_ng::{constants, scalar::Scalar};
 use rand_core::{CryptoRng, RngCore};
+use schnorrkel::{ExpansionMode, MiniSecretKey};
 use sha2::{digest::FixedOutput, Digest, Sha512};
+use zeroize::Zeroize;
+
+use crate::{Error, Result};
 
 use super::define_as_ref_u8_array;
 
+/// Signing context of sr25519, tendermint signs with an empty context.
+pub const SIGNING_CONTEXT: &[u8] = b"";
+
 #[derive(Debug, Clone)]
 pub struct SecretKey(pub [u8; 32]);
 define_as_ref_u8_array!(SecretKey);
 
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct PublicKey(pub [u8; 32]);
 define_as_ref_u8_array!(PublicKey);
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature(pub [u8; 64]);
+define_as_ref_u8_array!(Signature);
+
 impl SecretKey {
     pub fn generate(rng: impl RngCore + CryptoRng) -> Self {
         let mut rng = rng;
@@ -23,6 +40,22 @@ impl SecretKey {
         SecretKey(sk)
     }
 
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        SecretKey(seed)
+    }
+
+    /// Parse 32 bytes mini secret key.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let sk = bytes.try_into().map_err(|_| {
+            Error::InvalidKey(format!(
+                "sr25519 secret key must be 32 bytes, got {}",
+                bytes.len()
+            ))
+        })?;
+
+        Ok(SecretKey(sk))
+    }
+
     pub fn public_key(&self) -> PublicKey {
         let mut h = Sha512::default();
         h.update(self.0);
@@ -43,6 +76,30 @@ impl SecretKey {
 
         PublicKey(compressed.to_bytes())
     }
+
+    pub fn sign(&self, msg: &[u8]) -> Signature {
+        let keypair = MiniSecretKey::from_bytes(&self.0)
+            .expect("mini secret key must be 32 bytes")
+            .expand_to_keypair(ExpansionMode::Ed25519);
+
+        let signature = keypair.sign_simple(SIGNING_CONTEXT, msg);
+
+        Signature(signature.to_bytes())
+    }
+}
+
+impl PublicKey {
+    pub fn verify(&self, msg: &[u8], signature: &Signature) -> Result<()> {
+        let public_key =
+            schnorrkel::PublicKey::from_bytes(&self.0).map_err(|_| Error::InvalidSignature)?;
+
+        let signature =
+            schnorrkel::Signature::from_bytes(&signature.0).map_err(|_| Error::InvalidSignature)?;
+
+        public_key
+            .verify_simple(SIGNING_CONTEXT, msg, &signature)
+            .map_err(|_| Error::InvalidSignature)
+    }
 }
 
 fn divide_scalar_bytes_by_cofactor(scalar: &mut [u8; 32]) {
@@ -11,11 +11,40 @@ pub const VALIDATOR_KEY_FILE: &str = "config/priv_validator_key.json";
 pub const DATA_DIR: &str = "data";
 pub const GENESIS_FILE: &str = "genesis.json";
 pub const VALIDATOR_STATE_FILE: &str = "priv_validator_state.json";
+pub const SIGNER_STATE_FILE: &str = "signer_state.json";
 pub const WAL_FILE: &str = "cs.wal";
 
 pub const SOCKET_DIR: &str = "sockets";
 pub const RPC_UNIX_SOCKET_FILE: &str = "sockets/rpc";
 pub const APP_UNIX_SOCKET_FILE: &str = "sockets/app";
+pub const PRIVVAL_UNIX_SOCKET_FILE: &str = "sockets/privval";
+
+pub const STATE_SYNC_TEMP_DIR: &str = "statesync";
 
 pub const P2P_DIR: &str = "p2p";
 pub const ADDR_BOOK_FILE: &str = "p2p/addrbook.json";
+
+/// Only tendermint 0.33 accepts sr25519 as validator key, 0.34 and 0.37
+/// only have ed25519 and secp256k1 in `ABCIPubKeyTypesToNames`.
+#[cfg(all(feature = "td-ver-0-33", not(feature = "td-ver-0-34")))]
+pub const SR25519_VALIDATOR_KEY: bool = true;
+#[cfg(not(all(feature = "td-ver-0-33", not(feature = "td-ver-0-34"))))]
+pub const SR25519_VALIDATOR_KEY: bool = false;
+
+/// Tendermint 0.33 has no state sync.
+#[cfg(all(feature = "td-ver-0-33", not(feature = "td-ver-0-34")))]
+pub const STATE_SYNC: bool = false;
+#[cfg(not(all(feature = "td-ver-0-33", not(feature = "td-ver-0-34"))))]
+pub const STATE_SYNC: bool = true;
+
+/// Tendermint 0.33 has no priority mempool (v1).
+#[cfg(all(feature = "td-ver-0-33", not(feature = "td-ver-0-34")))]
+pub const PRIORITY_MEMPOOL: bool = false;
+#[cfg(not(all(feature = "td-ver-0-33", not(feature = "td-ver-0-34"))))]
+pub const PRIORITY_MEMPOOL: bool = true;
+
+/// Built-in signer speaks protobuf privval, tendermint 0.33 uses amino.
+#[cfg(all(feature = "td-ver-0-33", not(feature = "td-ver-0-34")))]
+pub const BUILTIN_SIGNER: bool = false;
+#[cfg(not(all(feature = "td-ver-0-33", not(feature = "td-ver-0-34"))))]
+pub const BUILTIN_SIGNER: bool = true;
@@ -15,6 +15,78 @@ pub enum Error {
     #[error("No stdout stream")]
     NoStdoutStream,
 
+    #[error("Duplicate validator address: {0}")]
+    DuplicateValidatorAddress(String),
+
+    #[error("Total voting power of validators is zero")]
+    ZeroTotalVotingPower,
+
+    #[error("Total voting power of validators overflow")]
+    VotingPowerOverflow,
+
+    #[error("Invalid genesis: {0}")]
+    InvalidGenesis(String),
+
+    #[error("Unsupported algorithm type: {0:?}")]
+    UnsupportedAlgorithmType(crate::AlgorithmType),
+
+    #[error("Invalid signature")]
+    InvalidSignature,
+
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+
+    #[error("Timeout: {0}")]
+    Timeout(String),
+
+    #[error("No free port in {0}")]
+    NoFreePort(String),
+
+    #[error("Trust hash mismatch: {0}")]
+    TrustHashMismatch(String),
+
+    #[error("RPC error: {0}")]
+    RpcError(String),
+
+    #[error("Invalid duration: {0}")]
+    InvalidDuration(String),
+
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+
+    #[error("Invalid testnet: {0}")]
+    InvalidTestnet(String),
+
+    #[error("No testnet node {0}")]
+    NoTestnetNode(usize),
+
+    #[error("No link between testnet node {0} and {1}")]
+    NoTestnetLink(usize, usize),
+
+    #[error("Unknown environment variable: {0}")]
+    UnknownEnvVar(String),
+
+    #[error("Invalid environment variable {0}: {1}")]
+    InvalidEnvVar(String, String),
+
+    #[error("Invalid peer address: {0}, format is id@host:port")]
+    InvalidPeerAddress(String),
+
+    #[error("Invalid keystore: {0}")]
+    InvalidKeystore(String),
+
+    #[error("Failed to decrypt keystore, wrong passphrase or corrupted data")]
+    KeystoreDecryptError,
+
+    #[error("Signer error: {0}")]
+    SignerError(String),
+
+    #[error("Refuse to double sign: {0}")]
+    DoubleSignError(String),
+
     #[error(transparent)]
     StdIoError(#[from] std::io::Error),
 
@@ -27,6 +99,24 @@ pub enum Error {
     #[error(transparent)]
     JsonError(#[from] serde_json::Error),
 
+    #[error(transparent)]
+    ParseEnumError(#[from] crate::config::ParseEnumError),
+
+    #[error(transparent)]
+    HexError(#[from] hex::FromHexError),
+
+    #[error(transparent)]
+    Base64Error(#[from] base64::DecodeError),
+
+    #[error(transparent)]
+    Bip32Error(#[from] bip32::Error),
+
+    #[error(transparent)]
+    Bip39Error(#[from] bip39::Error),
+
+    #[error(transparent)]
+    ProstDecodeError(#[from] prost::DecodeError),
+
     #[error(transparent)]
     PopenError(#[from] subprocess::PopenError),
 }
@@ -1,13 +1,26 @@
 //! Genesis type of tendermint
 
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 use time::{Duration, OffsetDateTime};
 
 use crate::{
-    crypto::{AlgorithmType, PublicKey},
-    model, utils,
+    config::define_build_mode_setter,
+    crypto::{Address, AlgorithmType, PublicKey},
+    model, utils, Error, Result,
 };
 
+/// Upper bound of the total voting power, same as tendermint's `MaxTotalVotingPower`.
+pub const MAX_TOTAL_VOTING_POWER: u64 = i64::MAX as u64 / 8;
+
+/// Max length of chain id.
+pub const MAX_CHAIN_ID_LEN: usize = 50;
+
+/// Max size of a block, 100MB.
+pub const MAX_BLOCK_SIZE_BYTES: u64 = 104857600;
+
 /// Genesis data
+#[derive(Debug, Clone)]
 pub struct Genesis<AppState> {
     /// Time of genesis
     pub genesis_time: OffsetDateTime,
@@ -31,6 +44,7 @@ pub struct Genesis<AppState> {
     pub app_state: Option<AppState>,
 }
 
+#[derive(Debug, Clone, Default)]
 pub struct ConsensusParams {
     /// Block size parameters
     pub block: Block,
@@ -46,6 +60,7 @@ pub struct ConsensusParams {
 }
 
 /// Block size parameters
+#[derive(Debug, Clone)]
 pub struct Block {
     /// Maximum number of bytes in a block
     pub max_bytes: u64,
@@ -57,6 +72,7 @@ pub struct Block {
     pub time_iota_ms: i64,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Evidence {
     /// Maximum allowed age for evidence to be collected
     pub max_age_num_blocks: u64,
@@ -68,16 +84,19 @@ pub struct Evidence {
     pub max_bytes: i64,
 }
 
+#[derive(Debug, Clone)]
 pub struct Validator {
     pub pub_key_types: Vec<AlgorithmType>,
 }
 
+#[derive(Debug, Clone, Default)]
 pub struct Version {
     pub app_version: Option<u64>,
 }
 
+#[derive(Debug, Clone)]
 pub struct ValidatorInfo {
-    pub address: [u8; 20],
+    pub address: Address,
 
     pub public_key: PublicKey,
 
@@ -91,67 +110,219 @@ pub struct ValidatorInfo {
     pub proposer_priority: i64,
 }
 
+impl Default for Block {
+    fn default() -> Self {
+        Self {
+            max_bytes: 22020096,
+            max_gas: -1,
+            time_iota_ms: 1000,
+        }
+    }
+}
+
+impl Default for Evidence {
+    fn default() -> Self {
+        Self {
+            max_age_num_blocks: 100000,
+            max_age_duration: Duration::days(2000),
+            max_bytes: 1048576,
+        }
+    }
+}
+
+impl Default for Validator {
+    fn default() -> Self {
+        Self {
+            pub_key_types: vec![AlgorithmType::Ed25519],
+        }
+    }
+}
+
 impl ValidatorInfo {
     pub fn generate(public_key: PublicKey) -> Self {
+        Self::new(public_key, 10)
+    }
+
+    pub fn new(public_key: PublicKey, power: u64) -> Self {
         Self {
             address: public_key.address(),
             public_key,
-            power: 10,
+            power,
             name: None,
             proposer_priority: 0,
         }
     }
+
+    define_build_mode_setter!(power, u64);
+
+    define_build_mode_setter!(name, String, option, name);
+
+    define_build_mode_setter!(proposer_priority, i64);
 }
 
-impl<AppState> Genesis<AppState> {
-    pub fn generate(public_key: PublicKey) -> Genesis<()> {
-        let block = Block {
-            max_bytes: 22020096,
-            max_gas: -1,
-            time_iota_ms: 1000,
-        };
+/// Builder of genesis with multiple validators
+pub struct GenesisBuilder<AppState> {
+    genesis_time: Option<OffsetDateTime>,
+    chain_id: String,
+    initial_height: i64,
+    consensus_params: ConsensusParams,
+    validators: Vec<ValidatorInfo>,
+    app_hash: Vec<u8>,
+    app_state: Option<AppState>,
+}
 
-        let evidence = Evidence {
-            max_age_num_blocks: 100000,
-            max_age_duration: Duration::days(2000),
-            max_bytes: 1048576,
-        };
+impl<AppState> Default for GenesisBuilder<AppState> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        let validator = Validator {
-            pub_key_types: vec![AlgorithmType::Ed25519],
-        };
+impl<AppState> GenesisBuilder<AppState> {
+    pub fn new() -> Self {
+        Self {
+            genesis_time: None,
+            chain_id: String::from("test-chain"),
+            initial_height: 0,
+            consensus_params: Default::default(),
+            validators: Vec::new(),
+            app_hash: Vec::new(),
+            app_state: None,
+        }
+    }
 
-        let consensus_params = ConsensusParams {
-            block,
-            evidence,
-            validator,
-            version: Version { app_version: None },
-        };
+    define_build_mode_setter!(chain_id, str);
+
+    define_build_mode_setter!(initial_height, i64);
 
-        let chain_id = String::from("test-chain");
+    define_build_mode_setter!(genesis_time, OffsetDateTime, option, genesis_time);
 
+    define_build_mode_setter!(app_hash, Vec<u8>);
+
+    define_build_mode_setter!(app_state, AppState, option, app_state);
+
+    define_build_mode_setter!(consensus_params, ConsensusParams);
+
+    define_build_mode_setter!(validators, Vec<ValidatorInfo>);
+
+    /// Append a validator
+    pub fn validator(self, validator: ValidatorInfo) -> Self {
+        let mut this = self;
+        this.validators.push(validator);
+        this
+    }
+
+    /// Check validators and build genesis
+    ///
+    /// Genesis time is set to now if not given.
+    pub fn build(self) -> Result<Genesis<AppState>> {
+        let mut total_power = 0u64;
+
+        for (i, v) in self.validators.iter().enumerate() {
+            if self.validators[..i].iter().any(|e| e.address == v.address) {
+                return Err(Error::DuplicateValidatorAddress(v.address.to_string()));
+            }
+
+            total_power = total_power
+                .checked_add(v.power)
+                .filter(|p| *p <= MAX_TOTAL_VOTING_POWER)
+                .ok_or(Error::VotingPowerOverflow)?;
+        }
+
+        if total_power == 0 {
+            return Err(Error::ZeroTotalVotingPower);
+        }
+
+        Ok(Genesis {
+            genesis_time: self.genesis_time.unwrap_or_else(OffsetDateTime::now_utc),
+            chain_id: self.chain_id,
+            initial_height: self.initial_height,
+            consensus_params: self.consensus_params,
+            validators: self.validators,
+            app_hash: self.app_hash,
+            app_state: self.app_state,
+        })
+    }
+}
+
+impl<AppState> Genesis<AppState> {
+    pub fn generate(public_key: PublicKey) -> Genesis<()> {
         let validator_info = ValidatorInfo::generate(public_key);
 
         Genesis {
             genesis_time: OffsetDateTime::now_utc(),
-            chain_id,
+            chain_id: String::from("test-chain"),
             initial_height: 0,
-            consensus_params,
+            consensus_params: Default::default(),
             validators: vec![validator_info],
             app_hash: Vec::new(),
             app_state: None,
         }
     }
 
-    pub(crate) fn into_model(self) -> model::Genesis<AppState> {
+    pub fn builder() -> GenesisBuilder<AppState> {
+        GenesisBuilder::new()
+    }
+
+    /// Check genesis in the same way as tendermint does before start
+    pub fn validate(&self) -> Result<()> {
+        if self.chain_id.is_empty() {
+            return Err(Error::InvalidGenesis(String::from("chain_id is empty")));
+        }
+
+        if self.chain_id.len() > MAX_CHAIN_ID_LEN {
+            return Err(Error::InvalidGenesis(format!(
+                "chain_id is too long, max is {}",
+                MAX_CHAIN_ID_LEN
+            )));
+        }
+
+        // Tendermint starts from height 1 if initial_height is 0.
+        if self.initial_height < 0 {
+            return Err(Error::InvalidGenesis(format!(
+                "initial_height cannot be negative, got {}",
+                self.initial_height
+            )));
+        }
+
+        self.consensus_params.validate()?;
+
+        let pub_key_types = &self.consensus_params.validator.pub_key_types;
+
+        for v in &self.validators {
+            if v.power == 0 {
+                return Err(Error::InvalidGenesis(format!(
+                    "validator {} has no voting power",
+                    v.address
+                )));
+            }
+
+            if v.address != v.public_key.address() {
+                return Err(Error::InvalidGenesis(format!(
+                    "validator {} address doesn't match its public key",
+                    v.address
+                )));
+            }
+
+            if !pub_key_types.contains(&v.public_key.algorithm_type()) {
+                return Err(Error::InvalidGenesis(format!(
+                    "validator {} public key type isn't in pub_key_types",
+                    v.address
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn to_model(&self) -> Result<model::Genesis<&AppState>> {
         let mut validators = Vec::with_capacity(self.validators.len());
 
-        for v in self.validators {
+        for v in &self.validators {
             let vi = model::ValidatorInfo {
-                address: hex::encode(v.address),
-                pub_key: v.public_key.into_model(),
+                address: v.address.to_hex(),
+                pub_key: v.public_key.clone().into_model(),
                 power: format!("{}", v.power),
-                name: v.name,
+                name: v.name.clone(),
                 proposer_priority: format!("{}", v.proposer_priority),
             };
 
@@ -166,12 +337,8 @@ impl<AppState> Genesis<AppState> {
 
         let evidence = model::EvidenceParams {
             max_bytes: format!("{}", self.consensus_params.evidence.max_bytes),
-            max_age_duration: format!(
-                "{}",
-                self.consensus_params
-                    .evidence
-                    .max_age_duration
-                    .whole_seconds()
+            max_age_duration: utils::build_duration_nanos(
+                self.consensus_params.evidence.max_age_duration,
             ),
             max_age_num_blocks: format!("{}", self.consensus_params.evidence.max_age_num_blocks),
         };
@@ -181,9 +348,9 @@ impl<AppState> Genesis<AppState> {
                 .consensus_params
                 .validator
                 .pub_key_types
-                .into_iter()
-                .map(|e| e.into())
-                .collect(),
+                .iter()
+                .map(|e| (*e).try_into())
+                .collect::<Result<_>>()?,
         };
 
         let version = model::VersionParams {
@@ -201,14 +368,379 @@ impl<AppState> Genesis<AppState> {
             version,
         };
 
-        model::Genesis {
-            chain_id: self.chain_id,
+        Ok(model::Genesis {
+            chain_id: self.chain_id.clone(),
             genesis_time: utils::to_rfc3339_nanos(self.genesis_time),
             initial_height: format!("{}", self.initial_height),
-            app_hash: hex::encode(self.app_hash),
+            app_hash: hex::encode(&self.app_hash),
             validators,
             consensus_params,
-            app_state: self.app_state,
+            app_state: self.app_state.as_ref(),
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<AppState> Genesis<AppState> {
+    pub(crate) fn from_model(m: model::Genesis<AppState>) -> Result<Self> {
+        let mut validators = Vec::with_capacity(m.validators.len());
+
+        for v in &m.validators {
+            validators.push(ValidatorInfo {
+                address: Address::from_hex(&v.address)?,
+                public_key: PublicKey::from_model(&v.pub_key)?,
+                power: parse_field("power", &v.power)?,
+                name: v.name.clone(),
+                proposer_priority: parse_field("proposer_priority", &v.proposer_priority)?,
+            });
+        }
+
+        let params = &m.consensus_params;
+
+        let consensus_params = ConsensusParams {
+            block: Block {
+                max_bytes: parse_field("block.max_bytes", &params.block.max_bytes)?,
+                max_gas: parse_field("block.max_gas", &params.block.max_gas)?,
+                time_iota_ms: parse_field("block.time_iota_ms", &params.block.time_iota_ms)?,
+            },
+            evidence: Evidence {
+                max_age_num_blocks: parse_field(
+                    "evidence.max_age_num_blocks",
+                    &params.evidence.max_age_num_blocks,
+                )?,
+                max_age_duration: Duration::nanoseconds(parse_field(
+                    "evidence.max_age_duration",
+                    &params.evidence.max_age_duration,
+                )?),
+                max_bytes: parse_field("evidence.max_bytes", &params.evidence.max_bytes)?,
+            },
+            validator: Validator {
+                pub_key_types: params
+                    .validator
+                    .pub_key_types
+                    .iter()
+                    .map(|e| (*e).into())
+                    .collect(),
+            },
+            version: Version {
+                app_version: params
+                    .version
+                    .app_version
+                    .as_ref()
+                    .map(|e| parse_field("version.app_version", e))
+                    .transpose()?,
+            },
+        };
+
+        Ok(Self {
+            genesis_time: utils::parse_rfc3339(&m.genesis_time)?,
+            chain_id: m.chain_id,
+            initial_height: parse_field("initial_height", &m.initial_height)?,
+            consensus_params,
+            validators,
+            app_hash: hex::decode(&m.app_hash)?,
+            app_state: m.app_state,
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+fn parse_field<T: std::str::FromStr>(name: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| Error::InvalidGenesis(format!("invalid {}: {}", name, value)))
+}
+
+/// Serialized as `genesis.json` of tendermint.
+#[cfg(feature = "serde")]
+impl<AppState: Serialize> Serialize for Genesis<AppState> {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        self.to_model()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, AppState: serde::Deserialize<'de>> serde::Deserialize<'de> for Genesis<AppState> {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        let m = model::Genesis::<AppState>::deserialize(deserializer)?;
+
+        Self::from_model(m).map_err(serde::de::Error::custom)
+    }
+}
+
+impl<AppState: Serialize> Genesis<AppState> {
+    /// JSON bytes of `genesis.json`
+    pub(crate) fn to_json(&self) -> Result<Vec<u8>> {
+        let m = self.to_model()?;
+        let cs = serde_json::to_string_pretty(&m)?;
+
+        Ok(cs.into_bytes())
+    }
+
+    /// SHA-256 of `genesis.json` written by `Tendermint::start`
+    ///
+    /// Nodes with the same genesis will get the same hash.
+    pub fn hash(&self) -> Result<[u8; 32]> {
+        let mut hash = [0u8; 32];
+
+        hash.copy_from_slice(&Sha256::digest(self.to_json()?));
+
+        Ok(hash)
+    }
+}
+
+impl ConsensusParams {
+    fn validate(&self) -> Result<()> {
+        let block = &self.block;
+
+        if block.max_bytes == 0 || block.max_bytes > MAX_BLOCK_SIZE_BYTES {
+            return Err(Error::InvalidGenesis(format!(
+                "block.max_bytes must be in (0, {}], got {}",
+                MAX_BLOCK_SIZE_BYTES, block.max_bytes
+            )));
+        }
+
+        if block.max_gas < -1 {
+            return Err(Error::InvalidGenesis(format!(
+                "block.max_gas must be greater or equal to -1, got {}",
+                block.max_gas
+            )));
+        }
+
+        if block.time_iota_ms <= 0 {
+            return Err(Error::InvalidGenesis(format!(
+                "block.time_iota_ms must be positive, got {}",
+                block.time_iota_ms
+            )));
+        }
+
+        let evidence = &self.evidence;
+
+        if evidence.max_age_num_blocks == 0 {
+            return Err(Error::InvalidGenesis(String::from(
+                "evidence.max_age_num_blocks must be positive",
+            )));
+        }
+
+        if !evidence.max_age_duration.is_positive() {
+            return Err(Error::InvalidGenesis(format!(
+                "evidence.max_age_duration must be positive, got {}",
+                evidence.max_age_duration
+            )));
+        }
+
+        if evidence.max_bytes < 0 || evidence.max_bytes as u64 > block.max_bytes {
+            return Err(Error::InvalidGenesis(format!(
+                "evidence.max_bytes must be in [0, block.max_bytes], got {}",
+                evidence.max_bytes
+            )));
+        }
+
+        let pub_key_types = &self.validator.pub_key_types;
+
+        if pub_key_types.is_empty() {
+            return Err(Error::InvalidGenesis(String::from(
+                "validator.pub_key_types is empty",
+            )));
+        }
+
+        for (i, ty) in pub_key_types.iter().enumerate() {
+            model::PublicKeyAlgorithm::try_from(*ty)?;
+
+            if pub_key_types[..i].contains(ty) {
+                return Err(Error::InvalidGenesis(format!(
+                    "duplicate pub_key_types: {:?}",
+                    ty
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::thread_rng;
+
+    use time::Duration;
+
+    use crate::{defined, model, Address, AlgorithmType, Error, Genesis, Keypair};
+
+    use super::{Evidence, GenesisBuilder, ValidatorInfo, MAX_TOTAL_VOTING_POWER};
+
+    fn validator(power: u64) -> ValidatorInfo {
+        let key = Keypair::generate(AlgorithmType::Ed25519, thread_rng());
+
+        ValidatorInfo::new(key.public_key, power)
+    }
+
+    #[test]
+    fn test_build_multi_validator() {
+        let genesis = GenesisBuilder::<()>::new()
+            .chain_id("multi-chain")
+            .validator(validator(10).name(String::from("v0")))
+            .validator(validator(20))
+            .build()
+            .unwrap();
+
+        assert_eq!(genesis.chain_id, "multi-chain");
+        assert_eq!(genesis.validators.len(), 2);
+    }
+
+    #[test]
+    fn test_build_reject_invalid_validators() {
+        let v = validator(10);
+        let r = GenesisBuilder::<()>::new()
+            .validator(v.clone())
+            .validator(v)
+            .build();
+        assert!(matches!(r, Err(Error::DuplicateValidatorAddress(_))));
+
+        let r = GenesisBuilder::<()>::new().validator(validator(0)).build();
+        assert!(matches!(r, Err(Error::ZeroTotalVotingPower)));
+
+        let r = GenesisBuilder::<()>::new().build();
+        assert!(matches!(r, Err(Error::ZeroTotalVotingPower)));
+
+        let r = GenesisBuilder::<()>::new()
+            .validator(validator(MAX_TOTAL_VOTING_POWER))
+            .validator(validator(1))
+            .build();
+        assert!(matches!(r, Err(Error::VotingPowerOverflow)));
+    }
+
+    #[test]
+    fn test_validate() {
+        let key = Keypair::generate(AlgorithmType::Ed25519, thread_rng());
+        let genesis = Genesis::<()>::generate(key.public_key.clone());
+        genesis.validate().unwrap();
+
+        let mut g = Genesis::<()>::generate(key.public_key.clone());
+        g.chain_id = "c".repeat(51);
+        assert!(matches!(g.validate(), Err(Error::InvalidGenesis(_))));
+
+        let mut g = Genesis::<()>::generate(key.public_key.clone());
+        g.initial_height = -1;
+        assert!(matches!(g.validate(), Err(Error::InvalidGenesis(_))));
+
+        let mut g = Genesis::<()>::generate(key.public_key.clone());
+        g.validators[0].address = Address::default();
+        assert!(matches!(g.validate(), Err(Error::InvalidGenesis(_))));
+
+        let mut g = Genesis::<()>::generate(key.public_key);
+        g.consensus_params.evidence.max_bytes = 22020097;
+        assert!(matches!(g.validate(), Err(Error::InvalidGenesis(_))));
+    }
+
+    #[test]
+    fn test_hash() {
+        let key = Keypair::generate(AlgorithmType::Ed25519, thread_rng());
+        let genesis = Genesis::<()>::generate(key.public_key);
+
+        let mut other = Genesis::<()>::generate(genesis.validators[0].public_key.clone());
+        other.genesis_time = genesis.genesis_time;
+        assert_eq!(genesis.hash().unwrap(), other.hash().unwrap());
+
+        other.chain_id = String::from("other-chain");
+        assert_ne!(genesis.hash().unwrap(), other.hash().unwrap());
+    }
+
+    fn evidence_round_trip(evidence: Evidence) {
+        let key = Keypair::generate(AlgorithmType::Ed25519, thread_rng());
+        let mut genesis = Genesis::<()>::generate(key.public_key);
+        genesis.consensus_params.evidence = evidence.clone();
+
+        let json = genesis.to_json().unwrap();
+        let m: model::Genesis<()> = serde_json::from_slice(&json).unwrap();
+        let e = m.consensus_params.evidence;
+
+        let parsed = Evidence {
+            max_age_num_blocks: e.max_age_num_blocks.parse().unwrap(),
+            max_age_duration: Duration::nanoseconds(e.max_age_duration.parse().unwrap()),
+            max_bytes: e.max_bytes.parse().unwrap(),
+        };
+
+        assert_eq!(parsed, evidence);
+    }
+
+    #[test]
+    fn test_evidence_round_trip() {
+        evidence_round_trip(Evidence::default());
+
+        evidence_round_trip(Evidence {
+            max_age_num_blocks: 1,
+            max_age_duration: Duration::new(1, 500),
+            max_bytes: 0,
+        });
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let keys = [
+            Keypair::generate(AlgorithmType::Ed25519, thread_rng()),
+            Keypair::generate(AlgorithmType::Secp256k1, thread_rng()),
+        ];
+
+        let genesis = GenesisBuilder::new()
+            .chain_id("test-chain")
+            .app_state(serde_json::json!({"accounts": []}))
+            .validator(ValidatorInfo::new(keys[0].public_key.clone(), 10))
+            .validator(ValidatorInfo::new(keys[1].public_key.clone(), 20))
+            .build()
+            .unwrap();
+
+        let json = serde_json::to_string(&genesis).unwrap();
+        let parsed: Genesis<serde_json::Value> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed.hash().unwrap(), genesis.hash().unwrap());
+        assert_eq!(parsed.validators[1].address, keys[1].public_key.address());
+        assert_eq!(parsed.app_state, genesis.app_state);
+
+        let keypair: Keypair =
+            serde_json::from_str(&serde_json::to_string(&keys[1]).unwrap()).unwrap();
+        assert_eq!(keypair.public_key.as_ref(), keys[1].public_key.as_ref());
+    }
+
+    #[test]
+    fn test_evidence_max_age_duration_nanos() {
+        let key = Keypair::generate(AlgorithmType::Ed25519, thread_rng());
+        let genesis = Genesis::<()>::generate(key.public_key);
+
+        let m = genesis.to_model().unwrap();
+
+        assert_eq!(
+            m.consensus_params.evidence.max_age_duration,
+            "172800000000000000"
+        );
+    }
+
+    #[test]
+    fn test_sr25519_pub_key_type() {
+        let key = Keypair::generate(AlgorithmType::Sr25519, thread_rng());
+        let mut genesis = Genesis::<()>::generate(key.public_key);
+        genesis.consensus_params.validator.pub_key_types = vec![AlgorithmType::Sr25519];
+
+        if defined::SR25519_VALIDATOR_KEY {
+            genesis.validate().unwrap();
+            genesis.to_json().unwrap();
+        } else {
+            assert!(matches!(
+                genesis.validate(),
+                Err(Error::UnsupportedAlgorithmType(AlgorithmType::Sr25519))
+            ));
+            assert!(matches!(
+                genesis.to_json(),
+                Err(Error::UnsupportedAlgorithmType(AlgorithmType::Sr25519))
+            ));
         }
     }
 }
@@ -13,11 +13,20 @@ pub use error::*;
 
 pub mod crypto;
 #[doc(inline)]
-pub use crypto::{AlgorithmType, Keypair, PublicKey, SecretKey};
+pub use crypto::{
+    Address, AlgorithmType, EncryptedKeypair, Keypair, PublicKey, SecretKey, Signature,
+};
 
 pub mod genesis;
 #[doc(inline)]
-pub use genesis::Genesis;
+pub use genesis::{Genesis, GenesisBuilder};
+
+pub mod privval;
+
+mod testnet;
+pub use testnet::*;
+
+pub(crate) mod rpc;
 
 pub(crate) mod model;
 
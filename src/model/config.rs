@@ -104,6 +104,7 @@ pub struct StateSync {
     pub(crate) discovery_time: String,
     pub(crate) temp_dir: String,
     pub(crate) chunk_request_timeout: String,
+    /// int32 in tendermint, quoted in its config template.
     pub(crate) chunk_fetchers: String,
 }
 
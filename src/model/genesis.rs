@@ -1,11 +1,11 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::crypto::AlgorithmType;
+use crate::{crypto::AlgorithmType, defined, Error, Result};
 
 use super::Key;
 
 /// Genesis data
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Genesis<AppState> {
     /// Time of genesis
     pub genesis_time: String,
@@ -29,7 +29,7 @@ pub struct Genesis<AppState> {
     pub app_state: Option<AppState>,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ConsensusParams {
     /// Block size parameters
     pub block: BlockSize,
@@ -45,7 +45,7 @@ pub struct ConsensusParams {
 }
 
 /// Block size parameters
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct BlockSize {
     /// Maximum number of bytes in a block
     pub max_bytes: String,
@@ -57,25 +57,25 @@ pub struct BlockSize {
     pub time_iota_ms: String,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EvidenceParams {
     /// Maximum allowed age for evidence to be collected
     pub max_age_num_blocks: String,
 
-    /// Max age duration
+    /// Max age duration, nanoseconds as integer string
     pub max_age_duration: String,
 
     /// Max bytes
     pub max_bytes: String,
 }
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ValidatorParams {
     pub pub_key_types: Vec<PublicKeyAlgorithm>,
 }
 
 /// Public key algorithms
-#[derive(Copy, Clone, Debug, Serialize)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum PublicKeyAlgorithm {
     /// ed25519
     #[serde(rename = "ed25519")]
@@ -84,25 +84,44 @@ pub enum PublicKeyAlgorithm {
     /// secp256k1
     #[serde(rename = "secp256k1")]
     Secp256k1,
+
+    /// sr25519
+    #[serde(rename = "sr25519")]
+    Sr25519,
 }
 
-impl From<AlgorithmType> for PublicKeyAlgorithm {
-    fn from(e: AlgorithmType) -> Self {
+impl From<PublicKeyAlgorithm> for AlgorithmType {
+    fn from(e: PublicKeyAlgorithm) -> Self {
+        match e {
+            PublicKeyAlgorithm::Ed25519 => AlgorithmType::Ed25519,
+            PublicKeyAlgorithm::Secp256k1 => AlgorithmType::Secp256k1,
+            PublicKeyAlgorithm::Sr25519 => AlgorithmType::Sr25519,
+        }
+    }
+}
+
+impl TryFrom<AlgorithmType> for PublicKeyAlgorithm {
+    type Error = Error;
+
+    fn try_from(e: AlgorithmType) -> Result<Self> {
         match e {
-            AlgorithmType::Ed25519 => PublicKeyAlgorithm::Ed25519,
-            AlgorithmType::Secp256k1 => PublicKeyAlgorithm::Secp256k1,
-            AlgorithmType::Sr25519 => panic!("no support this algorithm type."),
+            AlgorithmType::Ed25519 => Ok(PublicKeyAlgorithm::Ed25519),
+            AlgorithmType::Secp256k1 => Ok(PublicKeyAlgorithm::Secp256k1),
+            AlgorithmType::Sr25519 if defined::SR25519_VALIDATOR_KEY => {
+                Ok(PublicKeyAlgorithm::Sr25519)
+            }
+            _ => Err(Error::UnsupportedAlgorithmType(e)),
         }
     }
 }
 
-#[derive(Clone, Serialize, Debug, Default)]
+#[derive(Clone, Serialize, Deserialize, Debug, Default)]
 pub struct VersionParams {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub app_version: Option<String>,
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ValidatorInfo {
     /// Validator account address
     pub address: String,
@@ -1,4 +1,7 @@
+use std::ops::Deref;
+
 use serde::{Deserialize, Serialize};
+use zeroize::Zeroize;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Key {
@@ -7,9 +10,31 @@ pub struct Key {
     pub value: String,
 }
 
+/// Key of `priv_key`, value is zeroized on drop.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PrivKey(pub Key);
+
+impl Deref for PrivKey {
+    type Target = Key;
+
+    fn deref(&self) -> &Key {
+        &self.0
+    }
+}
+
+impl Drop for PrivKey {
+    fn drop(&mut self) {
+        self.0.value.zeroize();
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Keypair {
-    pub address: String,
-    pub priv_key: Key,
-    pub pub_key: Key,
+    /// `node_key.json` of tendermint only has `priv_key`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    pub priv_key: PrivKey,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pub_key: Option<Key>,
 }
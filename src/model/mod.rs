@@ -7,5 +7,8 @@ pub use genesis::*;
 mod config;
 pub use config::*;
 
+mod keystore;
+pub use keystore::*;
+
 mod validator_state;
 pub use validator_state::*;
@@ -0,0 +1,12 @@
+//! Built-in remote signer of tendermint
+
+pub(crate) mod proto;
+
+mod signer;
+pub use signer::*;
+
+mod state;
+pub use state::*;
+
+mod secret_connection;
+pub use secret_connection::*;
@@ -0,0 +1,383 @@
+//! Protobuf messages of tendermint's privval protocol
+//!
+//! Hand written from `tendermint/privval/types.proto`,
+//! `tendermint/types/types.proto`, `tendermint/types/canonical.proto` and
+//! `tendermint/p2p/conn.proto`.
+
+use prost::Message as _;
+
+/// Type of signed message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, prost::Enumeration)]
+#[repr(i32)]
+pub enum SignedMsgType {
+    Unknown = 0,
+    Prevote = 1,
+    Precommit = 2,
+    Proposal = 32,
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct Timestamp {
+    #[prost(int64, tag = "1")]
+    pub seconds: i64,
+    #[prost(int32, tag = "2")]
+    pub nanos: i32,
+}
+
+impl Timestamp {
+    /// `time.Time{}` of golang, which is `0001-01-01T00:00:00Z`.
+    pub fn zero() -> Self {
+        Self {
+            seconds: -62135596800,
+            nanos: 0,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct PartSetHeader {
+    #[prost(uint32, tag = "1")]
+    pub total: u32,
+    #[prost(bytes = "vec", tag = "2")]
+    pub hash: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct BlockId {
+    #[prost(bytes = "vec", tag = "1")]
+    pub hash: Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub part_set_header: Option<PartSetHeader>,
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct Vote {
+    #[prost(enumeration = "SignedMsgType", tag = "1")]
+    pub r#type: i32,
+    #[prost(int64, tag = "2")]
+    pub height: i64,
+    #[prost(int32, tag = "3")]
+    pub round: i32,
+    #[prost(message, optional, tag = "4")]
+    pub block_id: Option<BlockId>,
+    #[prost(message, optional, tag = "5")]
+    pub timestamp: Option<Timestamp>,
+    #[prost(bytes = "vec", tag = "6")]
+    pub validator_address: Vec<u8>,
+    #[prost(int32, tag = "7")]
+    pub validator_index: i32,
+    #[prost(bytes = "vec", tag = "8")]
+    pub signature: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct Proposal {
+    #[prost(enumeration = "SignedMsgType", tag = "1")]
+    pub r#type: i32,
+    #[prost(int64, tag = "2")]
+    pub height: i64,
+    #[prost(int32, tag = "3")]
+    pub round: i32,
+    #[prost(int32, tag = "4")]
+    pub pol_round: i32,
+    #[prost(message, optional, tag = "5")]
+    pub block_id: Option<BlockId>,
+    #[prost(message, optional, tag = "6")]
+    pub timestamp: Option<Timestamp>,
+    #[prost(bytes = "vec", tag = "7")]
+    pub signature: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct CanonicalPartSetHeader {
+    #[prost(uint32, tag = "1")]
+    pub total: u32,
+    #[prost(bytes = "vec", tag = "2")]
+    pub hash: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct CanonicalBlockId {
+    #[prost(bytes = "vec", tag = "1")]
+    pub hash: Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub part_set_header: Option<CanonicalPartSetHeader>,
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct CanonicalVote {
+    #[prost(enumeration = "SignedMsgType", tag = "1")]
+    pub r#type: i32,
+    #[prost(sfixed64, tag = "2")]
+    pub height: i64,
+    #[prost(sfixed64, tag = "3")]
+    pub round: i64,
+    #[prost(message, optional, tag = "4")]
+    pub block_id: Option<CanonicalBlockId>,
+    #[prost(message, optional, tag = "5")]
+    pub timestamp: Option<Timestamp>,
+    #[prost(string, tag = "6")]
+    pub chain_id: String,
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct CanonicalProposal {
+    #[prost(enumeration = "SignedMsgType", tag = "1")]
+    pub r#type: i32,
+    #[prost(sfixed64, tag = "2")]
+    pub height: i64,
+    #[prost(sfixed64, tag = "3")]
+    pub round: i64,
+    #[prost(int64, tag = "4")]
+    pub pol_round: i64,
+    #[prost(message, optional, tag = "5")]
+    pub block_id: Option<CanonicalBlockId>,
+    #[prost(message, optional, tag = "6")]
+    pub timestamp: Option<Timestamp>,
+    #[prost(string, tag = "7")]
+    pub chain_id: String,
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct PublicKey {
+    #[prost(oneof = "public_key::Sum", tags = "1, 2")]
+    pub sum: Option<public_key::Sum>,
+}
+
+pub mod public_key {
+    #[derive(Clone, PartialEq, Eq, prost::Oneof)]
+    pub enum Sum {
+        #[prost(bytes, tag = "1")]
+        Ed25519(Vec<u8>),
+        #[prost(bytes, tag = "2")]
+        Secp256k1(Vec<u8>),
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct RemoteSignerError {
+    #[prost(int32, tag = "1")]
+    pub code: i32,
+    #[prost(string, tag = "2")]
+    pub description: String,
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct PubKeyRequest {
+    #[prost(string, tag = "1")]
+    pub chain_id: String,
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct PubKeyResponse {
+    #[prost(message, optional, tag = "1")]
+    pub pub_key: Option<PublicKey>,
+    #[prost(message, optional, tag = "2")]
+    pub error: Option<RemoteSignerError>,
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct SignVoteRequest {
+    #[prost(message, optional, tag = "1")]
+    pub vote: Option<Vote>,
+    #[prost(string, tag = "2")]
+    pub chain_id: String,
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct SignedVoteResponse {
+    #[prost(message, optional, tag = "1")]
+    pub vote: Option<Vote>,
+    #[prost(message, optional, tag = "2")]
+    pub error: Option<RemoteSignerError>,
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct SignProposalRequest {
+    #[prost(message, optional, tag = "1")]
+    pub proposal: Option<Proposal>,
+    #[prost(string, tag = "2")]
+    pub chain_id: String,
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct SignedProposalResponse {
+    #[prost(message, optional, tag = "1")]
+    pub proposal: Option<Proposal>,
+    #[prost(message, optional, tag = "2")]
+    pub error: Option<RemoteSignerError>,
+}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct PingRequest {}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct PingResponse {}
+
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct Message {
+    #[prost(oneof = "message::Sum", tags = "1, 2, 3, 4, 5, 6, 7, 8")]
+    pub sum: Option<message::Sum>,
+}
+
+pub mod message {
+    #[derive(Clone, PartialEq, Eq, prost::Oneof)]
+    pub enum Sum {
+        #[prost(message, tag = "1")]
+        PubKeyRequest(super::PubKeyRequest),
+        #[prost(message, tag = "2")]
+        PubKeyResponse(super::PubKeyResponse),
+        #[prost(message, tag = "3")]
+        SignVoteRequest(super::SignVoteRequest),
+        #[prost(message, tag = "4")]
+        SignedVoteResponse(super::SignedVoteResponse),
+        #[prost(message, tag = "5")]
+        SignProposalRequest(super::SignProposalRequest),
+        #[prost(message, tag = "6")]
+        SignedProposalResponse(super::SignedProposalResponse),
+        #[prost(message, tag = "7")]
+        PingRequest(super::PingRequest),
+        #[prost(message, tag = "8")]
+        PingResponse(super::PingResponse),
+    }
+}
+
+/// `google.protobuf.BytesValue`, ephemeral key of secret connection.
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct BytesValue {
+    #[prost(bytes = "vec", tag = "1")]
+    pub value: Vec<u8>,
+}
+
+/// `tendermint.p2p.AuthSigMessage` of secret connection.
+#[derive(Clone, PartialEq, Eq, prost::Message)]
+pub struct AuthSigMessage {
+    #[prost(message, optional, tag = "1")]
+    pub pub_key: Option<PublicKey>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub sig: Vec<u8>,
+}
+
+fn canonical_block_id(block_id: &Option<BlockId>) -> Option<CanonicalBlockId> {
+    let block_id = block_id.clone().unwrap_or_default();
+    let part_set_header = block_id.part_set_header.unwrap_or_default();
+
+    if block_id.hash.is_empty() && part_set_header.total == 0 && part_set_header.hash.is_empty() {
+        return None;
+    }
+
+    Some(CanonicalBlockId {
+        hash: block_id.hash,
+        part_set_header: Some(CanonicalPartSetHeader {
+            total: part_set_header.total,
+            hash: part_set_header.hash,
+        }),
+    })
+}
+
+/// Bytes to sign of vote, same as `VoteSignBytes` of tendermint.
+pub fn vote_sign_bytes(chain_id: &str, vote: &Vote) -> Vec<u8> {
+    let canonical = CanonicalVote {
+        r#type: vote.r#type,
+        height: vote.height,
+        round: vote.round as i64,
+        block_id: canonical_block_id(&vote.block_id),
+        timestamp: Some(vote.timestamp.clone().unwrap_or_else(Timestamp::zero)),
+        chain_id: String::from(chain_id),
+    };
+
+    canonical.encode_length_delimited_to_vec()
+}
+
+/// Bytes to sign of proposal, same as `ProposalSignBytes` of tendermint.
+pub fn proposal_sign_bytes(chain_id: &str, proposal: &Proposal) -> Vec<u8> {
+    let canonical = CanonicalProposal {
+        r#type: SignedMsgType::Proposal as i32,
+        height: proposal.height,
+        round: proposal.round as i64,
+        pol_round: proposal.pol_round as i64,
+        block_id: canonical_block_id(&proposal.block_id),
+        timestamp: Some(proposal.timestamp.clone().unwrap_or_else(Timestamp::zero)),
+        chain_id: String::from(chain_id),
+    };
+
+    canonical.encode_length_delimited_to_vec()
+}
+
+/// Timestamp of `last` if it differs from `new` only by timestamp.
+///
+/// Both are length delimited `CanonicalVote`, same as
+/// `checkVotesOnlyDifferByTimestamp` of tendermint.
+pub fn votes_only_differ_by_timestamp(last: &[u8], new: &[u8]) -> Option<Timestamp> {
+    let mut last = CanonicalVote::decode_length_delimited(last).ok()?;
+    let mut new = CanonicalVote::decode_length_delimited(new).ok()?;
+
+    let timestamp = last.timestamp.take();
+    new.timestamp = None;
+
+    if last == new {
+        timestamp
+    } else {
+        None
+    }
+}
+
+/// Timestamp of `last` if it differs from `new` only by timestamp.
+///
+/// Both are length delimited `CanonicalProposal`, same as
+/// `checkProposalsOnlyDifferByTimestamp` of tendermint.
+pub fn proposals_only_differ_by_timestamp(last: &[u8], new: &[u8]) -> Option<Timestamp> {
+    let mut last = CanonicalProposal::decode_length_delimited(last).ok()?;
+    let mut new = CanonicalProposal::decode_length_delimited(new).ok()?;
+
+    let timestamp = last.timestamp.take();
+    new.timestamp = None;
+
+    if last == new {
+        timestamp
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{vote_sign_bytes, SignedMsgType, Vote};
+
+    #[test]
+    fn test_vote_sign_bytes() {
+        // Test vectors from `TestVoteSignBytesTestVectors` of tendermint.
+        let timestamp = [
+            0x2a, 0xb, 0x8, 0x80, 0x92, 0xb8, 0xc3, 0x98, 0xfe, 0xff, 0xff, 0xff, 0x1,
+        ];
+
+        let mut expect = vec![0xd];
+        expect.extend_from_slice(&timestamp);
+        assert_eq!(vote_sign_bytes("", &Vote::default()), expect);
+
+        let vote = Vote {
+            height: 1,
+            round: 1,
+            r#type: SignedMsgType::Precommit as i32,
+            ..Default::default()
+        };
+        let mut expect = vec![0x21, 0x8, 0x2];
+        expect.extend_from_slice(&[0x11, 0x1, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0]);
+        expect.extend_from_slice(&[0x19, 0x1, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0]);
+        expect.extend_from_slice(&timestamp);
+        assert_eq!(vote_sign_bytes("", &vote), expect);
+
+        let vote = Vote {
+            height: 1,
+            round: 1,
+            ..Default::default()
+        };
+        let mut expect = vec![0x2e];
+        expect.extend_from_slice(&[0x11, 0x1, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0]);
+        expect.extend_from_slice(&[0x19, 0x1, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0]);
+        expect.extend_from_slice(&timestamp);
+        expect.extend_from_slice(&[0x32, 0xd]);
+        expect.extend_from_slice(b"test_chain_id");
+        assert_eq!(vote_sign_bytes("test_chain_id", &vote), expect);
+    }
+}
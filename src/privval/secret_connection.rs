@@ -0,0 +1,390 @@
+use std::{
+    cmp,
+    io::{self, Read, Write},
+};
+
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, KeyInit};
+use curve25519_dalek_ng::{
+    constants::X25519_BASEPOINT, montgomery::MontgomeryPoint, scalar::Scalar,
+};
+use hmac::{Hmac, Mac};
+use merlin::Transcript;
+use rand_core::{CryptoRng, RngCore};
+use sha2::Sha256;
+use zeroize::Zeroize;
+
+use crate::{
+    crypto::{ed25519, Keypair},
+    AlgorithmType, Error, PublicKey, Result, Signature,
+};
+
+use super::{
+    proto::{self, public_key::Sum},
+    signer::{read_delimited, write_delimited},
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+const DATA_LEN_SIZE: usize = 4;
+const DATA_MAX_SIZE: usize = 1024;
+const TOTAL_FRAME_SIZE: usize = DATA_MAX_SIZE + DATA_LEN_SIZE;
+const AEAD_SIZE_OVERHEAD: usize = 16;
+const SEALED_FRAME_SIZE: usize = TOTAL_FRAME_SIZE + AEAD_SIZE_OVERHEAD;
+
+/// Max size of handshake message, same as tendermint.
+const MAX_HANDSHAKE_MSG_SIZE: usize = 1024 * 1024;
+
+const LABEL_TRANSCRIPT: &[u8] = b"TENDERMINT_SECRET_CONNECTION_TRANSCRIPT_HASH";
+const LABEL_EPHEMERAL_LOWER: &[u8] = b"EPHEMERAL_LOWER_PUBLIC_KEY";
+const LABEL_EPHEMERAL_UPPER: &[u8] = b"EPHEMERAL_UPPER_PUBLIC_KEY";
+const LABEL_DH_SECRET: &[u8] = b"DH_SECRET";
+const LABEL_SECRET_CONNECTION_MAC: &[u8] = b"SECRET_CONNECTION_MAC";
+const KEY_AND_CHALLENGE_GEN: &[u8] = b"TENDERMINT_SECRET_CONNECTION_KEY_AND_CHALLENGE_GEN";
+
+/// Tendermint's authenticated encryption connection (STS protocol)
+///
+/// Ephemeral X25519 keys are exchanged first, then each side signs the
+/// handshake transcript with its ed25519 identity key. Data is sent in
+/// ChaCha20-Poly1305 sealed frames of 1024 bytes.
+pub struct SecretConnection<T> {
+    io: T,
+    remote_public_key: PublicKey,
+    send_cipher: ChaCha20Poly1305,
+    recv_cipher: ChaCha20Poly1305,
+    send_nonce: u64,
+    recv_nonce: u64,
+    recv_buffer: Vec<u8>,
+}
+
+impl<T: Read + Write> SecretConnection<T> {
+    /// Perform handshake over `io`, `keypair` must be ed25519.
+    pub fn new(io: T, keypair: &Keypair, rng: impl RngCore + CryptoRng) -> Result<Self> {
+        let mut io = io;
+        let mut rng = rng;
+
+        let ty = keypair.public_key.algorithm_type();
+        if ty != AlgorithmType::Ed25519 {
+            return Err(Error::UnsupportedAlgorithmType(ty));
+        }
+
+        let mut local_eph_secret = [0u8; 32];
+        rng.fill_bytes(&mut local_eph_secret);
+        let local_eph_public = x25519(&local_eph_secret, &X25519_BASEPOINT.0);
+
+        write_delimited(
+            &mut io,
+            &proto::BytesValue {
+                value: local_eph_public.to_vec(),
+            },
+        )?;
+        let remote: proto::BytesValue = read_delimited(&mut io, MAX_HANDSHAKE_MSG_SIZE)?;
+        let remote_eph_public: [u8; 32] = remote
+            .value
+            .as_slice()
+            .try_into()
+            .map_err(|_| handshake_error("invalid ephemeral public key"))?;
+
+        let local_is_least = local_eph_public < remote_eph_public;
+        let (lower, upper) = if local_is_least {
+            (&local_eph_public, &remote_eph_public)
+        } else {
+            (&remote_eph_public, &local_eph_public)
+        };
+
+        let mut transcript = Transcript::new(LABEL_TRANSCRIPT);
+        transcript.append_message(LABEL_EPHEMERAL_LOWER, lower);
+        transcript.append_message(LABEL_EPHEMERAL_UPPER, upper);
+
+        let mut dh_secret = x25519(&local_eph_secret, &remote_eph_public);
+        local_eph_secret.zeroize();
+
+        if dh_secret == [0u8; 32] {
+            return Err(handshake_error("low order ephemeral public key"));
+        }
+
+        transcript.append_message(LABEL_DH_SECRET, &dh_secret);
+
+        let (mut recv_secret, mut send_secret) = derive_secrets(&dh_secret, local_is_least);
+        dh_secret.zeroize();
+
+        let mut challenge = [0u8; 32];
+        transcript.challenge_bytes(LABEL_SECRET_CONNECTION_MAC, &mut challenge);
+
+        let mut this = Self {
+            io,
+            remote_public_key: keypair.public_key.clone(),
+            send_cipher: ChaCha20Poly1305::new(&send_secret.into()),
+            recv_cipher: ChaCha20Poly1305::new(&recv_secret.into()),
+            send_nonce: 0,
+            recv_nonce: 0,
+            recv_buffer: Vec::new(),
+        };
+        send_secret.zeroize();
+        recv_secret.zeroize();
+
+        let auth = proto::AuthSigMessage {
+            pub_key: Some(proto::PublicKey {
+                sum: Some(Sum::Ed25519(keypair.public_key.as_ref().to_vec())),
+            }),
+            sig: keypair.sign(&challenge).as_ref().to_vec(),
+        };
+        write_delimited(&mut this, &auth)?;
+
+        let remote: proto::AuthSigMessage = read_delimited(&mut this, MAX_HANDSHAKE_MSG_SIZE)?;
+
+        let remote_public_key = match remote.pub_key.and_then(|k| k.sum) {
+            Some(Sum::Ed25519(k)) => {
+                let k: [u8; 32] = k
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| handshake_error("invalid remote public key"))?;
+                PublicKey::Ed25519(ed25519::PublicKey(k))
+            }
+            _ => return Err(handshake_error("remote public key must be ed25519")),
+        };
+
+        let signature = Signature::from_bytes(AlgorithmType::Ed25519, &remote.sig)?;
+        remote_public_key.verify(&challenge, &signature)?;
+
+        this.remote_public_key = remote_public_key;
+
+        Ok(this)
+    }
+
+    /// Authenticated identity key of remote peer.
+    pub fn remote_public_key(&self) -> &PublicKey {
+        &self.remote_public_key
+    }
+}
+
+impl<T: Read> Read for SecretConnection<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.recv_buffer.is_empty() {
+            let mut sealed = [0u8; SEALED_FRAME_SIZE];
+            self.io.read_exact(&mut sealed)?;
+
+            let frame = self
+                .recv_cipher
+                .decrypt(&nonce(self.recv_nonce), sealed.as_slice())
+                .map_err(|_| {
+                    io::Error::new(io::ErrorKind::InvalidData, "failed to decrypt frame")
+                })?;
+            self.recv_nonce = next_nonce(self.recv_nonce)?;
+
+            let mut len = [0u8; DATA_LEN_SIZE];
+            len.copy_from_slice(&frame[..DATA_LEN_SIZE]);
+            let len = u32::from_le_bytes(len) as usize;
+
+            if len > DATA_MAX_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "frame length is greater than max size",
+                ));
+            }
+
+            self.recv_buffer = frame[DATA_LEN_SIZE..DATA_LEN_SIZE + len].to_vec();
+        }
+
+        let n = cmp::min(buf.len(), self.recv_buffer.len());
+        buf[..n].copy_from_slice(&self.recv_buffer[..n]);
+        self.recv_buffer.drain(..n);
+
+        Ok(n)
+    }
+}
+
+impl<T: Write> Write for SecretConnection<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for chunk in buf.chunks(DATA_MAX_SIZE) {
+            let mut frame = [0u8; TOTAL_FRAME_SIZE];
+            frame[..DATA_LEN_SIZE].copy_from_slice(&(chunk.len() as u32).to_le_bytes());
+            frame[DATA_LEN_SIZE..DATA_LEN_SIZE + chunk.len()].copy_from_slice(chunk);
+
+            let sealed = self
+                .send_cipher
+                .encrypt(&nonce(self.send_nonce), frame.as_slice())
+                .map_err(|_| io::Error::other("failed to encrypt frame"))?;
+            self.send_nonce = next_nonce(self.send_nonce)?;
+
+            self.io.write_all(&sealed)?;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.io.flush()
+    }
+}
+
+fn handshake_error(msg: &str) -> Error {
+    Error::SignerError(format!("secret connection handshake failed: {}", msg))
+}
+
+fn x25519(secret: &[u8; 32], public: &[u8; 32]) -> [u8; 32] {
+    let mut bits = *secret;
+
+    bits[0] &= 248;
+    bits[31] &= 127;
+    bits[31] |= 64;
+
+    let scalar = Scalar::from_bits(bits);
+    bits.zeroize();
+
+    (MontgomeryPoint(*public) * scalar).0
+}
+
+/// Derive `(recv_secret, send_secret)` from DH secret by HKDF-SHA256, same as
+/// `deriveSecrets` of tendermint.
+fn derive_secrets(dh_secret: &[u8; 32], local_is_least: bool) -> ([u8; 32], [u8; 32]) {
+    let okm = hkdf_sha256(dh_secret, &[], KEY_AND_CHALLENGE_GEN, 96);
+
+    let mut first = [0u8; 32];
+    let mut second = [0u8; 32];
+    first.copy_from_slice(&okm[..32]);
+    second.copy_from_slice(&okm[32..64]);
+
+    if local_is_least {
+        (first, second)
+    } else {
+        (second, first)
+    }
+}
+
+/// HKDF-SHA256 of RFC 5869, empty salt means zero filled.
+fn hkdf_sha256(ikm: &[u8], salt: &[u8], info: &[u8], len: usize) -> Vec<u8> {
+    let zero_salt = [0u8; 32];
+    let salt = if salt.is_empty() {
+        &zero_salt[..]
+    } else {
+        salt
+    };
+
+    let mut mac = <HmacSha256 as Mac>::new_from_slice(salt).expect("hmac accepts any key length");
+    mac.update(ikm);
+    let prk = mac.finalize().into_bytes();
+
+    let mut okm = Vec::with_capacity(len);
+    let mut block = Vec::new();
+    let mut counter = 1u8;
+
+    while okm.len() < len {
+        let mut mac =
+            <HmacSha256 as Mac>::new_from_slice(&prk).expect("hmac accepts any key length");
+        mac.update(&block);
+        mac.update(info);
+        mac.update(&[counter]);
+        block = mac.finalize().into_bytes().to_vec();
+
+        okm.extend_from_slice(&block);
+        counter += 1;
+    }
+
+    okm.truncate(len);
+    okm
+}
+
+/// Counter is little endian in last 8 bytes of nonce.
+fn nonce(counter: u64) -> chacha20poly1305::Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[4..].copy_from_slice(&counter.to_le_bytes());
+    nonce.into()
+}
+
+fn next_nonce(counter: u64) -> io::Result<u64> {
+    counter
+        .checked_add(1)
+        .ok_or_else(|| io::Error::other("nonce overflow"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        thread,
+    };
+
+    use rand::thread_rng;
+
+    use crate::{AlgorithmType, Keypair};
+
+    use super::{derive_secrets, hkdf_sha256, SecretConnection};
+
+    #[test]
+    fn test_hkdf_sha256() {
+        // Test case 1 of RFC 5869.
+        let ikm = [0x0bu8; 22];
+        let salt = hex::decode("000102030405060708090a0b0c").unwrap();
+        let info = hex::decode("f0f1f2f3f4f5f6f7f8f9").unwrap();
+
+        assert_eq!(
+            hex::encode(hkdf_sha256(&ikm, &salt, &info, 42)),
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865"
+        );
+    }
+
+    #[test]
+    fn test_derive_secrets() {
+        // First line of `TestDeriveSecretsAndChallenge.golden` of tendermint.
+        let mut dh_secret = [0u8; 32];
+        dh_secret.copy_from_slice(
+            &hex::decode("9fe4a5a73df12dbd8659b1d9280873fe993caefec6b0ebc2686dd65027148e03")
+                .unwrap(),
+        );
+
+        let (recv, send) = derive_secrets(&dh_secret, true);
+        assert_eq!(
+            hex::encode(recv),
+            "80a83ad6afcb6f8175192e41973aed31dd75e3c106f813d986d9567a4865eb2f"
+        );
+        assert_eq!(
+            hex::encode(send),
+            "96362a04f628a0666d9866147326898bb0847b8db8680263ad19e6336d4eed9e"
+        );
+    }
+
+    #[test]
+    fn test_handshake_and_transfer() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server_key = Keypair::generate(AlgorithmType::Ed25519, thread_rng());
+        let client_key = Keypair::generate(AlgorithmType::Ed25519, thread_rng());
+        let server_public_key = server_key.public_key.clone();
+        let client_public_key = client_key.public_key.clone();
+
+        let data: Vec<u8> = (0..5000u32).map(|i| i as u8).collect();
+        let expect = data.clone();
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut conn = SecretConnection::new(stream, &server_key, thread_rng()).unwrap();
+            assert_eq!(
+                conn.remote_public_key().as_ref(),
+                client_public_key.as_ref()
+            );
+
+            let mut buf = vec![0u8; expect.len()];
+            conn.read_exact(&mut buf).unwrap();
+            assert_eq!(buf, expect);
+
+            conn.write_all(b"pong").unwrap();
+        });
+
+        let stream = TcpStream::connect(addr).unwrap();
+        let mut conn = SecretConnection::new(stream, &client_key, thread_rng()).unwrap();
+        assert_eq!(
+            conn.remote_public_key().as_ref(),
+            server_public_key.as_ref()
+        );
+
+        conn.write_all(&data).unwrap();
+
+        let mut buf = [0u8; 4];
+        conn.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"pong");
+
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,602 @@
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use rand_core::OsRng;
+
+use crate::{crypto::Keypair, AlgorithmType, Error, PublicKey, Result};
+
+use super::{
+    proto::{self, message::Sum},
+    LastSignState, SecretConnection, STEP_PRECOMMIT, STEP_PREVOTE, STEP_PROPOSE,
+};
+
+/// Max size of privval message, same as tendermint.
+const MAX_MSG_SIZE: usize = 1024 * 10;
+
+/// Interval to redial tendermint.
+const DIAL_INTERVAL: Duration = Duration::from_millis(100);
+
+pub(crate) trait Connection: Read + Write + Send {}
+
+impl<T: Read + Write + Send> Connection for T {}
+
+/// Remote signer speaking tendermint's privval protocol
+///
+/// Tendermint listens on `priv_validator_laddr`, signer dials it and answers
+/// requests. Signed height/round/step is persisted in `state` to refuse
+/// double signing.
+pub struct Signer {
+    keypair: Keypair,
+    chain_id: String,
+    state: LastSignState,
+    identity: Keypair,
+}
+
+impl Signer {
+    pub fn new(keypair: Keypair, chain_id: &str, state: LastSignState) -> Self {
+        Self {
+            keypair,
+            chain_id: String::from(chain_id),
+            state,
+            identity: Keypair::generate(AlgorithmType::Ed25519, OsRng),
+        }
+    }
+
+    /// Ed25519 key to authenticate secret connection over TCP, random by
+    /// default.
+    pub fn identity(mut self, identity: Keypair) -> Self {
+        self.identity = identity;
+        self
+    }
+
+    /// Dial `addr` and serve requests in background thread until stopped.
+    ///
+    /// Address is `unix:///path/to/socket` or `tcp://host:port`, TCP is
+    /// wrapped in secret connection.
+    pub fn spawn(self, addr: &str) -> Result<SignerHandle> {
+        let addr = SignerAddress::parse(addr)?;
+
+        let ty = self.identity.public_key.algorithm_type();
+        if matches!(addr, SignerAddress::Tcp(_)) && ty != AlgorithmType::Ed25519 {
+            return Err(Error::UnsupportedAlgorithmType(ty));
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+
+        let mut this = self;
+
+        let thread = thread::spawn(move || {
+            while !thread_stop.load(Ordering::SeqCst) {
+                let conn = match addr.dial(&this.identity) {
+                    Ok(c) => c,
+                    Err(e) if is_retryable(&e) => {
+                        log::debug!("Signer failed to dial tendermint, retry: {:?}", e);
+                        thread::sleep(DIAL_INTERVAL);
+                        continue;
+                    }
+                    Err(e) => {
+                        log::warn!("Signer failed to dial tendermint, stop: {:?}", e);
+                        break;
+                    }
+                };
+
+                log::info!("Signer connected to tendermint");
+
+                if let Err(e) = this.serve(conn) {
+                    log::debug!("Signer connection closed: {:?}", e);
+                }
+            }
+        });
+
+        Ok(SignerHandle {
+            stop,
+            thread: Some(thread),
+        })
+    }
+
+    fn serve(&mut self, conn: Box<dyn Connection>) -> Result<()> {
+        let mut conn = conn;
+
+        loop {
+            let request = read_message(&mut conn)?;
+            let response = self.handle(request)?;
+            write_message(&mut conn, &response)?;
+        }
+    }
+
+    /// Answer request, error on responses or empty message, which means
+    /// peer doesn't speak privval and connection should be closed.
+    pub(crate) fn handle(&mut self, request: proto::Message) -> Result<proto::Message> {
+        let sum = match request.sum {
+            Some(Sum::PubKeyRequest(req)) => {
+                let res = self
+                    .check_chain_id(&req.chain_id)
+                    .and_then(|_| public_key_to_proto(&self.keypair.public_key));
+
+                let (pub_key, error) = split_result(res);
+
+                Sum::PubKeyResponse(proto::PubKeyResponse { pub_key, error })
+            }
+            Some(Sum::SignVoteRequest(req)) => {
+                let res = self.sign_vote(&req.chain_id, req.vote.unwrap_or_default());
+
+                let (vote, error) = split_result(res);
+
+                Sum::SignedVoteResponse(proto::SignedVoteResponse {
+                    vote: Some(vote.unwrap_or_default()),
+                    error,
+                })
+            }
+            Some(Sum::SignProposalRequest(req)) => {
+                let res = self.sign_proposal(&req.chain_id, req.proposal.unwrap_or_default());
+
+                let (proposal, error) = split_result(res);
+
+                Sum::SignedProposalResponse(proto::SignedProposalResponse {
+                    proposal: Some(proposal.unwrap_or_default()),
+                    error,
+                })
+            }
+            Some(Sum::PingRequest(_)) => Sum::PingResponse(proto::PingResponse {}),
+            sum => {
+                log::warn!("Signer received unexpected message: {:?}", sum);
+
+                return Err(Error::SignerError(format!(
+                    "unexpected privval message: {:?}",
+                    sum
+                )));
+            }
+        };
+
+        Ok(proto::Message { sum: Some(sum) })
+    }
+
+    fn check_chain_id(&self, chain_id: &str) -> Result<()> {
+        if chain_id != self.chain_id {
+            return Err(Error::SignerError(format!(
+                "chain id mismatch, expect {}, got {}",
+                self.chain_id, chain_id
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn sign_vote(&mut self, chain_id: &str, vote: proto::Vote) -> Result<proto::Vote> {
+        self.check_chain_id(chain_id)?;
+
+        let mut vote = vote;
+
+        let step = if vote.r#type == proto::SignedMsgType::Prevote as i32 {
+            STEP_PREVOTE
+        } else if vote.r#type == proto::SignedMsgType::Precommit as i32 {
+            STEP_PRECOMMIT
+        } else {
+            return Err(Error::SignerError(format!(
+                "unknown vote type: {}",
+                vote.r#type
+            )));
+        };
+
+        let sign_bytes = proto::vote_sign_bytes(chain_id, &vote);
+
+        if self.state.check_hrs(vote.height, vote.round, step)? {
+            let (last_sign_bytes, last_signature) = self.last_signed();
+
+            if sign_bytes == last_sign_bytes {
+                vote.signature = last_signature;
+            } else if let Some(timestamp) =
+                proto::votes_only_differ_by_timestamp(&last_sign_bytes, &sign_bytes)
+            {
+                vote.timestamp = Some(timestamp);
+                vote.signature = last_signature;
+            } else {
+                return Err(Error::DoubleSignError(String::from(
+                    "conflicting vote at same height/round/step",
+                )));
+            }
+
+            return Ok(vote);
+        }
+
+        let signature = self.keypair.sign(&sign_bytes).as_ref().to_vec();
+        self.state
+            .update(vote.height, vote.round, step, sign_bytes, signature.clone())?;
+        vote.signature = signature;
+
+        Ok(vote)
+    }
+
+    fn sign_proposal(
+        &mut self,
+        chain_id: &str,
+        proposal: proto::Proposal,
+    ) -> Result<proto::Proposal> {
+        self.check_chain_id(chain_id)?;
+
+        let mut proposal = proposal;
+
+        let sign_bytes = proto::proposal_sign_bytes(chain_id, &proposal);
+
+        if self
+            .state
+            .check_hrs(proposal.height, proposal.round, STEP_PROPOSE)?
+        {
+            let (last_sign_bytes, last_signature) = self.last_signed();
+
+            if sign_bytes == last_sign_bytes {
+                proposal.signature = last_signature;
+            } else if let Some(timestamp) =
+                proto::proposals_only_differ_by_timestamp(&last_sign_bytes, &sign_bytes)
+            {
+                proposal.timestamp = Some(timestamp);
+                proposal.signature = last_signature;
+            } else {
+                return Err(Error::DoubleSignError(String::from(
+                    "conflicting proposal at same height/round",
+                )));
+            }
+
+            return Ok(proposal);
+        }
+
+        let signature = self.keypair.sign(&sign_bytes).as_ref().to_vec();
+        self.state.update(
+            proposal.height,
+            proposal.round,
+            STEP_PROPOSE,
+            sign_bytes,
+            signature.clone(),
+        )?;
+        proposal.signature = signature;
+
+        Ok(proposal)
+    }
+
+    fn last_signed(&self) -> (Vec<u8>, Vec<u8>) {
+        (
+            self.state.sign_bytes.clone().unwrap_or_default(),
+            self.state.signature.clone().unwrap_or_default(),
+        )
+    }
+}
+
+fn split_result<T>(res: Result<T>) -> (Option<T>, Option<proto::RemoteSignerError>) {
+    match res {
+        Ok(v) => (Some(v), None),
+        Err(e) => {
+            log::warn!("Signer refused request: {}", e);
+
+            let error = proto::RemoteSignerError {
+                code: 0,
+                description: e.to_string(),
+            };
+
+            (None, Some(error))
+        }
+    }
+}
+
+fn public_key_to_proto(public_key: &PublicKey) -> Result<proto::PublicKey> {
+    let sum = match public_key {
+        PublicKey::Ed25519(k) => proto::public_key::Sum::Ed25519(k.0.to_vec()),
+        PublicKey::Secp256k1(k) => proto::public_key::Sum::Secp256k1(k.0.to_vec()),
+        PublicKey::Sr25519(_) => {
+            return Err(Error::UnsupportedAlgorithmType(public_key.algorithm_type()))
+        }
+    };
+
+    Ok(proto::PublicKey { sum: Some(sum) })
+}
+
+/// Read uvarint length-delimited message.
+pub(crate) fn read_message(conn: &mut impl Read) -> Result<proto::Message> {
+    read_delimited(conn, MAX_MSG_SIZE)
+}
+
+/// Write uvarint length-delimited message.
+pub(crate) fn write_message(conn: &mut impl Write, msg: &proto::Message) -> Result<()> {
+    write_delimited(conn, msg)
+}
+
+pub(crate) fn read_delimited<M: prost::Message + Default>(
+    conn: &mut impl Read,
+    max_size: usize,
+) -> Result<M> {
+    let mut len = 0usize;
+
+    for i in 0..10 {
+        let mut byte = [0u8; 1];
+        conn.read_exact(&mut byte)?;
+
+        len |= ((byte[0] & 0x7f) as usize) << (7 * i);
+
+        if byte[0] & 0x80 == 0 {
+            break;
+        }
+    }
+
+    if len > max_size {
+        return Err(Error::SignerError(format!(
+            "message too large: {} bytes",
+            len
+        )));
+    }
+
+    let mut buf = vec![0u8; len];
+    conn.read_exact(&mut buf)?;
+
+    Ok(M::decode(buf.as_slice())?)
+}
+
+pub(crate) fn write_delimited(conn: &mut impl Write, msg: &impl prost::Message) -> Result<()> {
+    conn.write_all(&msg.encode_length_delimited_to_vec())?;
+    conn.flush()?;
+
+    Ok(())
+}
+
+enum SignerAddress {
+    #[cfg(unix)]
+    Unix(String),
+    Tcp(String),
+}
+
+impl SignerAddress {
+    fn parse(addr: &str) -> Result<Self> {
+        #[cfg(unix)]
+        if let Some(path) = addr.strip_prefix("unix://") {
+            return Ok(Self::Unix(String::from(path)));
+        }
+
+        if let Some(host) = addr.strip_prefix("tcp://") {
+            return Ok(Self::Tcp(String::from(host)));
+        }
+
+        Err(Error::SignerError(format!(
+            "unsupported signer address: {}",
+            addr
+        )))
+    }
+
+    fn dial(&self, identity: &Keypair) -> Result<Box<dyn Connection>> {
+        match self {
+            #[cfg(unix)]
+            Self::Unix(path) => Ok(Box::new(std::os::unix::net::UnixStream::connect(path)?)),
+            Self::Tcp(host) => {
+                let stream = TcpStream::connect(host)?;
+                let conn = SecretConnection::new(stream, identity, OsRng)?;
+
+                Ok(Box::new(conn))
+            }
+        }
+    }
+}
+
+/// Tendermint may not listen yet, so refused or reset connections are
+/// retried. Bad address, handshake and authentication failures are not.
+fn is_retryable(e: &Error) -> bool {
+    match e {
+        Error::StdIoError(e) => !matches!(
+            e.kind(),
+            std::io::ErrorKind::InvalidInput | std::io::ErrorKind::PermissionDenied
+        ),
+        _ => false,
+    }
+}
+
+/// Handle of running signer
+#[derive(Debug)]
+pub struct SignerHandle {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SignerHandle {
+    /// Stop redialing. The thread exits once tendermint closes connection.
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+
+    /// Stop and wait signer thread exit.
+    pub fn join(&mut self) {
+        self.stop();
+
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                log::error!("Signer thread panicked");
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{io::Write, net::TcpListener, os::unix::net::UnixListener};
+
+    use rand::thread_rng;
+    use tempfile::tempdir;
+
+    use crate::{AlgorithmType, Keypair, Signature};
+
+    use super::{
+        proto::{self, message::Sum},
+        read_message, write_message, LastSignState, SecretConnection, Signer,
+    };
+
+    fn request(sum: Sum) -> proto::Message {
+        proto::Message { sum: Some(sum) }
+    }
+
+    #[test]
+    fn test_signer_over_unix_socket() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("privval");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let keypair = Keypair::generate(AlgorithmType::Ed25519, thread_rng());
+        let public_key = keypair.public_key.clone();
+
+        let state = LastSignState::load(dir.path().join("state.json")).unwrap();
+
+        let mut handle = Signer::new(keypair, "test-chain", state)
+            .spawn(&format!("unix://{}", path.display()))
+            .unwrap();
+
+        let (mut conn, _) = listener.accept().unwrap();
+
+        let req = request(Sum::PubKeyRequest(proto::PubKeyRequest {
+            chain_id: String::from("test-chain"),
+        }));
+        write_message(&mut conn, &req).unwrap();
+        match read_message(&mut conn).unwrap().sum {
+            Some(Sum::PubKeyResponse(res)) => {
+                let expect = proto::public_key::Sum::Ed25519(public_key.as_ref().to_vec());
+                assert_eq!(res.pub_key.unwrap().sum, Some(expect));
+            }
+            _ => unreachable!(),
+        }
+
+        let vote = proto::Vote {
+            r#type: proto::SignedMsgType::Prevote as i32,
+            height: 1,
+            ..Default::default()
+        };
+        let req = request(Sum::SignVoteRequest(proto::SignVoteRequest {
+            vote: Some(vote.clone()),
+            chain_id: String::from("test-chain"),
+        }));
+        write_message(&mut conn, &req).unwrap();
+        match read_message(&mut conn).unwrap().sum {
+            Some(Sum::SignedVoteResponse(res)) => {
+                assert!(res.error.is_none());
+
+                let signed = res.vote.unwrap();
+                let signature =
+                    Signature::from_bytes(AlgorithmType::Ed25519, &signed.signature).unwrap();
+                let sign_bytes = proto::vote_sign_bytes("test-chain", &vote);
+                public_key.verify(&sign_bytes, &signature).unwrap();
+            }
+            _ => unreachable!(),
+        }
+
+        // Same vote is signed again with cached signature, conflicting one
+        // is refused.
+        let mut signatures = Vec::new();
+        for hash in [vec![], vec![], vec![1u8; 32]] {
+            let mut vote = vote.clone();
+            vote.block_id = Some(proto::BlockId {
+                hash,
+                part_set_header: None,
+            });
+            let req = request(Sum::SignVoteRequest(proto::SignVoteRequest {
+                vote: Some(vote),
+                chain_id: String::from("test-chain"),
+            }));
+            write_message(&mut conn, &req).unwrap();
+            match read_message(&mut conn).unwrap().sum {
+                Some(Sum::SignedVoteResponse(res)) => match res.error {
+                    Some(_) => signatures.push(None),
+                    None => signatures.push(Some(res.vote.unwrap().signature)),
+                },
+                _ => unreachable!(),
+            }
+        }
+        assert!(signatures[0].is_some());
+        assert_eq!(signatures[0], signatures[1]);
+        assert!(signatures[2].is_none());
+
+        let req = request(Sum::SignProposalRequest(proto::SignProposalRequest {
+            proposal: Some(proto::Proposal::default()),
+            chain_id: String::from("other-chain"),
+        }));
+        write_message(&mut conn, &req).unwrap();
+        match read_message(&mut conn).unwrap().sum {
+            Some(Sum::SignedProposalResponse(res)) => assert!(res.error.is_some()),
+            _ => unreachable!(),
+        }
+
+        write_message(&mut conn, &request(Sum::PingRequest(proto::PingRequest {}))).unwrap();
+        assert!(matches!(
+            read_message(&mut conn).unwrap().sum,
+            Some(Sum::PingResponse(_))
+        ));
+
+        // Response isn't a request, signer closes connection.
+        write_message(
+            &mut conn,
+            &request(Sum::PingResponse(proto::PingResponse {})),
+        )
+        .unwrap();
+        assert!(read_message(&mut conn).is_err());
+
+        drop(conn);
+        drop(listener);
+        handle.join();
+    }
+
+    #[test]
+    fn test_signer_over_tcp() {
+        let dir = tempdir().unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let keypair = Keypair::generate(AlgorithmType::Ed25519, thread_rng());
+        let identity = Keypair::generate(AlgorithmType::Ed25519, thread_rng());
+        let identity_public_key = identity.public_key.clone();
+        let state = LastSignState::load(dir.path().join("state.json")).unwrap();
+
+        let mut handle = Signer::new(keypair, "test-chain", state)
+            .identity(identity)
+            .spawn(&format!("tcp://{}", addr))
+            .unwrap();
+
+        let (stream, _) = listener.accept().unwrap();
+        let node_key = Keypair::generate(AlgorithmType::Ed25519, thread_rng());
+        let mut conn = SecretConnection::new(stream, &node_key, thread_rng()).unwrap();
+        assert_eq!(
+            conn.remote_public_key().as_ref(),
+            identity_public_key.as_ref()
+        );
+
+        write_message(&mut conn, &request(Sum::PingRequest(proto::PingRequest {}))).unwrap();
+        assert!(matches!(
+            read_message(&mut conn).unwrap().sum,
+            Some(Sum::PingResponse(_))
+        ));
+
+        drop(conn);
+        drop(listener);
+        handle.join();
+    }
+
+    #[test]
+    fn test_signer_stops_on_handshake_failure() {
+        let dir = tempdir().unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let keypair = Keypair::generate(AlgorithmType::Ed25519, thread_rng());
+        let state = LastSignState::load(dir.path().join("state.json")).unwrap();
+
+        let mut handle = Signer::new(keypair, "test-chain", state)
+            .spawn(&format!("tcp://{}", addr))
+            .unwrap();
+
+        // Delimited `BytesValue` with 1 byte ephemeral key.
+        let (mut stream, _) = listener.accept().unwrap();
+        stream.write_all(&[3, 0x0a, 1, 0]).unwrap();
+
+        // Thread exits without stop, instead of redialing.
+        handle.thread.take().unwrap().join().unwrap();
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{model, Error, Result};
+
+/// Step of proposal.
+pub const STEP_PROPOSE: i8 = 1;
+/// Step of prevote.
+pub const STEP_PREVOTE: i8 = 2;
+/// Step of precommit.
+pub const STEP_PRECOMMIT: i8 = 3;
+
+/// Last signed height/round/step of signer
+///
+/// Stored in the same format as tendermint's `priv_validator_state.json`, so
+/// an existing state file can be loaded directly.
+#[derive(Debug)]
+pub struct LastSignState {
+    path: PathBuf,
+    pub height: i64,
+    pub round: i32,
+    pub step: i8,
+    pub signature: Option<Vec<u8>>,
+    pub sign_bytes: Option<Vec<u8>>,
+}
+
+impl LastSignState {
+    /// Load state from `path`, create an empty one if not exists.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+
+        if !path.exists() {
+            let state = Self {
+                path,
+                height: 0,
+                round: 0,
+                step: 0,
+                signature: None,
+                sign_bytes: None,
+            };
+            state.persist()?;
+
+            return Ok(state);
+        }
+
+        let m: model::ValidatorState = serde_json::from_slice(&fs::read(&path)?)?;
+
+        let height = m
+            .height
+            .parse()
+            .map_err(|_| Error::SignerError(format!("invalid height in state: {}", m.height)))?;
+
+        let signature = match m.signature {
+            Some(s) if !s.is_empty() => Some(base64::decode(s)?),
+            _ => None,
+        };
+        let sign_bytes = match m.signbytes {
+            Some(s) if !s.is_empty() => Some(hex::decode(s)?),
+            _ => None,
+        };
+
+        if sign_bytes.is_some() && signature.is_none() {
+            return Err(Error::SignerError(String::from(
+                "state has sign bytes but no signature",
+            )));
+        }
+
+        Ok(Self {
+            path,
+            height,
+            round: m.round as i32,
+            step: m.step as i8,
+            signature,
+            sign_bytes,
+        })
+    }
+
+    /// Check height/round/step against last signed one, same as `CheckHRS`
+    /// of tendermint.
+    ///
+    /// Returns `true` if it equals last signed one, error if regression.
+    pub fn check_hrs(&self, height: i64, round: i32, step: i8) -> Result<bool> {
+        if self.height > height {
+            return Err(Error::DoubleSignError(format!(
+                "height regression, got {}, last height {}",
+                height, self.height
+            )));
+        }
+
+        if self.height == height {
+            if self.round > round {
+                return Err(Error::DoubleSignError(format!(
+                    "round regression at height {}, got {}, last round {}",
+                    height, round, self.round
+                )));
+            }
+
+            if self.round == round {
+                if self.step > step {
+                    return Err(Error::DoubleSignError(format!(
+                        "step regression at height {} round {}, got {}, last step {}",
+                        height, round, step, self.step
+                    )));
+                }
+
+                if self.step == step {
+                    if self.sign_bytes.is_none() {
+                        return Err(Error::DoubleSignError(String::from("no sign bytes found")));
+                    }
+
+                    return Ok(true);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Record signed message and persist it before signature is released.
+    pub fn update(
+        &mut self,
+        height: i64,
+        round: i32,
+        step: i8,
+        sign_bytes: Vec<u8>,
+        signature: Vec<u8>,
+    ) -> Result<()> {
+        self.height = height;
+        self.round = round;
+        self.step = step;
+        self.sign_bytes = Some(sign_bytes);
+        self.signature = Some(signature);
+
+        self.persist()
+    }
+
+    fn persist(&self) -> Result<()> {
+        let m = model::ValidatorState {
+            height: self.height.to_string(),
+            round: self.round as i64,
+            step: self.step as i64,
+            signature: self.signature.as_ref().map(base64::encode),
+            signbytes: self.sign_bytes.as_ref().map(hex::encode_upper),
+        };
+
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+
+        let mut file = File::create(&tmp)?;
+        file.write_all(&serde_json::to_vec_pretty(&m)?)?;
+        file.sync_all()?;
+
+        fs::rename(&tmp, &self.path)?;
+
+        #[cfg(unix)]
+        if let Some(dir) = self.path.parent() {
+            let dir = if dir.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                dir
+            };
+            File::open(dir)?.sync_all()?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::{LastSignState, STEP_PRECOMMIT, STEP_PREVOTE, STEP_PROPOSE};
+
+    #[test]
+    fn test_check_hrs_and_reload() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("priv_validator_state.json");
+
+        let mut state = LastSignState::load(&path).unwrap();
+        assert!(!state.check_hrs(1, 0, STEP_PROPOSE).unwrap());
+
+        state
+            .update(5, 1, STEP_PREVOTE, vec![1, 2, 3], vec![4, 5, 6])
+            .unwrap();
+
+        assert!(state.check_hrs(4, 9, STEP_PRECOMMIT).is_err());
+        assert!(state.check_hrs(5, 0, STEP_PRECOMMIT).is_err());
+        assert!(state.check_hrs(5, 1, STEP_PROPOSE).is_err());
+        assert!(state.check_hrs(5, 1, STEP_PREVOTE).unwrap());
+        assert!(!state.check_hrs(5, 1, STEP_PRECOMMIT).unwrap());
+        assert!(!state.check_hrs(6, 0, STEP_PROPOSE).unwrap());
+
+        let state = LastSignState::load(&path).unwrap();
+        assert_eq!(state.height, 5);
+        assert_eq!(state.round, 1);
+        assert_eq!(state.step, STEP_PREVOTE);
+        assert_eq!(state.sign_bytes, Some(vec![1, 2, 3]));
+        assert_eq!(state.signature, Some(vec![4, 5, 6]));
+    }
+
+    #[test]
+    fn test_load_tendermint_state() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("priv_validator_state.json");
+
+        std::fs::write(&path, r#"{"height":"0","round":0,"step":0}"#).unwrap();
+        let state = LastSignState::load(&path).unwrap();
+        assert_eq!(state.height, 0);
+        assert!(state.sign_bytes.is_none());
+
+        std::fs::write(
+            &path,
+            r#"{"height":"12","round":2,"step":3,"signature":"AQID","signbytes":"0A0B"}"#,
+        )
+        .unwrap();
+        let state = LastSignState::load(&path).unwrap();
+        assert_eq!(state.height, 12);
+        assert_eq!(state.round, 2);
+        assert_eq!(state.step, STEP_PRECOMMIT);
+        assert_eq!(state.signature, Some(vec![1, 2, 3]));
+        assert_eq!(state.sign_bytes, Some(vec![0x0a, 0x0b]));
+    }
+}
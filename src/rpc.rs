@@ -0,0 +1,123 @@
+//! Minimal client of tendermint's JSON-RPC over HTTP GET
+
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+use serde_json::Value;
+
+use crate::{Error, Result};
+
+/// Timeout of read and write.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// GET `path` from RPC at `laddr` and return `result` of response.
+///
+/// `laddr` is `host:port`, `tcp://host:port`, `http://host:port` or
+/// `unix:///path`.
+pub(crate) fn get(laddr: &str, path: &str) -> Result<Value> {
+    let request = format!("GET {} HTTP/1.0\r\nHost: localhost\r\n\r\n", path);
+
+    let mut response = Vec::new();
+
+    let tcp_addr = if laddr.contains("://") {
+        laddr
+            .strip_prefix("tcp://")
+            .or_else(|| laddr.strip_prefix("http://"))
+    } else {
+        Some(laddr)
+    };
+
+    if let Some(addr) = tcp_addr {
+        let mut stream = TcpStream::connect(addr.trim_end_matches('/'))?;
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        stream.write_all(request.as_bytes())?;
+        stream.read_to_end(&mut response)?;
+    } else if let Some(path) = laddr.strip_prefix("unix://") {
+        #[cfg(unix)]
+        {
+            let mut stream = std::os::unix::net::UnixStream::connect(path)?;
+            stream.set_read_timeout(Some(TIMEOUT))?;
+            stream.set_write_timeout(Some(TIMEOUT))?;
+
+            stream.write_all(request.as_bytes())?;
+            stream.read_to_end(&mut response)?;
+        }
+
+        #[cfg(not(unix))]
+        return Err(Error::RpcError(format!("unsupported address: {}", path)));
+    } else {
+        return Err(Error::RpcError(format!("unsupported address: {}", laddr)));
+    }
+
+    let pos = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| Error::RpcError(String::from("malformed http response")))?;
+
+    let status = String::from_utf8_lossy(&response[..pos])
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    let body: Value = serde_json::from_slice(&response[pos + 4..])
+        .map_err(|_| Error::RpcError(format!("unexpected response: {}", status)))?;
+
+    if let Some(error) = body.get("error") {
+        return Err(Error::RpcError(error.to_string()));
+    }
+
+    body.get("result")
+        .cloned()
+        .ok_or_else(|| Error::RpcError(format!("no result in response: {}", status)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+        thread,
+    };
+
+    use super::get;
+
+    fn serve_once(body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 1024];
+            let _ = stream.read(&mut buf).unwrap();
+
+            write!(
+                stream,
+                "HTTP/1.0 200 OK\r\nContent-Type: application/json\r\n\r\n{}",
+                body
+            )
+            .unwrap();
+        });
+
+        format!("tcp://{}", addr)
+    }
+
+    #[test]
+    fn test_get() {
+        let laddr = serve_once(
+            r#"{"jsonrpc":"2.0","id":-1,"result":{"sync_info":{"latest_block_height":"7"}}}"#,
+        );
+        let result = get(&laddr, "/status").unwrap();
+        assert_eq!(result["sync_info"]["latest_block_height"], "7");
+
+        let laddr = serve_once(r#"{"jsonrpc":"2.0","id":-1,"error":{"code":-32603}}"#);
+        assert!(get(&laddr, "/status").is_err());
+
+        assert!(get("udp://127.0.0.1:1", "/status").is_err());
+    }
+}
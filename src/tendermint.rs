@@ -10,7 +10,13 @@ use serde::Serialize;
 use subprocess::{Exec, Popen, PopenConfig};
 use tempfile::tempdir;
 
-use crate::{crypto::Keypair, defined, model, Config, Error, Genesis, Result};
+use crate::{
+    config::{self, split_port, PortStrategy},
+    crypto::{EncryptedKeypair, Keypair},
+    defined, model,
+    privval::{LastSignState, Signer, SignerHandle},
+    Config, Error, Genesis, Result,
+};
 
 #[derive(RustEmbed)]
 #[folder = "$OUT_DIR/build"]
@@ -24,9 +30,30 @@ pub struct Tendermint {
 
     tendermint_child: Option<Popen>,
 
+    node_id: Option<String>,
+
+    laddrs: Option<ListenAddrs>,
+
+    /// Ports chosen by port strategy, released after node exit.
+    reserved_ports: Vec<u16>,
+
+    /// Plaintext validator key is decrypted from keystore, remove it after node exit.
+    remove_validator_key: bool,
+
+    signer: Option<SignerHandle>,
+
     cleanup: bool,
 }
 
+/// Listen addresses chosen when tendermint started
+#[derive(Debug)]
+struct ListenAddrs {
+    p2p: String,
+    rpc: String,
+    pprof: Option<String>,
+    prometheus: Option<String>,
+}
+
 impl Drop for Tendermint {
     fn drop(&mut self) {
         if self.cleanup {
@@ -45,9 +72,15 @@ impl Tendermint {
 
         if let Some(mut child) = c {
             child.terminate()?;
+            #[cfg(unix)]
+            Self::send_signal(&child, libc::SIGCONT)?;
             child.wait()?;
         }
 
+        self.stop_signer();
+        self.release_ports();
+        self.remove_plaintext_validator_key()?;
+
         fs::remove_dir_all(self.get_work_dir())?;
 
         Ok(())
@@ -104,6 +137,35 @@ impl Tendermint {
     pub fn get_app_path(&self) -> PathBuf {
         self.get_work_dir().join(defined::APP_UNIX_SOCKET_FILE)
     }
+
+    /// Node ID of started tendermint
+    pub fn node_id(&self) -> Result<&str> {
+        self.node_id.as_deref().ok_or(Error::NoTendermintStart)
+    }
+
+    fn laddrs(&self) -> Result<&ListenAddrs> {
+        self.laddrs.as_ref().ok_or(Error::NoTendermintStart)
+    }
+
+    /// P2P listen address of started tendermint
+    pub fn p2p_laddr(&self) -> Result<&str> {
+        Ok(&self.laddrs()?.p2p)
+    }
+
+    /// RPC listen address of started tendermint
+    pub fn rpc_laddr(&self) -> Result<&str> {
+        Ok(&self.laddrs()?.rpc)
+    }
+
+    /// pprof listen address of started tendermint, `None` if disabled.
+    pub fn pprof_laddr(&self) -> Result<Option<&str>> {
+        Ok(self.laddrs()?.pprof.as_deref())
+    }
+
+    /// Prometheus listen address of started tendermint, `None` if disabled.
+    pub fn prometheus_laddr(&self) -> Result<Option<&str>> {
+        Ok(self.laddrs()?.prometheus.as_deref())
+    }
 }
 
 impl Tendermint {
@@ -114,6 +176,11 @@ impl Tendermint {
             Self {
                 work_dir,
                 tendermint_child: None,
+                node_id: None,
+                laddrs: None,
+                reserved_ports: Vec::new(),
+                remove_validator_key: false,
+                signer: None,
                 cleanup: true,
             }
         };
@@ -165,32 +232,75 @@ impl Tendermint {
             fs::create_dir_all(self.get_work_dir().join(defined::DATA_DIR))?;
         }
 
-        let mut file = File::create(self.get_config_path())?;
-        let cm = config.into_model(self.get_work_dir().to_str().ok_or(Error::PathUtf8Error)?);
+        if let Some(state_sync) = &config.state_sync {
+            state_sync.validate_with_genesis(&genesis)?;
+        }
+
+        let builtin_signer = config.builtin_signer;
+        let port_strategy = config.port_strategy;
+
+        let cm = config.into_model(self.get_work_dir().to_str().ok_or(Error::PathUtf8Error)?)?;
         let cs = toml::to_string_pretty(&cm)?;
+        let mut file = File::create(self.get_config_path())?;
         file.write_all(&cs.into_bytes())?;
 
-        let mut file = File::create(self.get_node_key_path())?;
-        let m = node_key.into_model();
-        let cs = serde_json::to_string_pretty(&m)?;
-        file.write_all(&cs.into_bytes())?;
+        if cm.statesync.enable {
+            fs::create_dir_all(&cm.statesync.temp_dir)?;
+        }
 
-        let mut file = File::create(self.get_validator_key_path())?;
-        let m = validator_key.into_model();
-        let cs = serde_json::to_string_pretty(&m)?;
-        file.write_all(&cs.into_bytes())?;
+        node_key.save(self.get_node_key_path())?;
+        self.node_id = Some(node_key.node_id());
+
+        self.laddrs = Some(ListenAddrs {
+            p2p: cm.p2p.laddr.clone(),
+            rpc: cm.rpc.laddr.clone(),
+            pprof: Some(cm.rpc.pprof_laddr.clone()).filter(|a| !a.is_empty()),
+            prometheus: Some(cm.instrumentation.prometheus_listen_addr.clone())
+                .filter(|_| cm.instrumentation.prometheus),
+        });
+
+        if port_strategy != PortStrategy::Fixed {
+            let laddrs = self.laddrs()?;
+
+            self.reserved_ports = [&laddrs.p2p, &laddrs.rpc]
+                .into_iter()
+                .chain(laddrs.pprof.as_ref())
+                .chain(laddrs.prometheus.as_ref())
+                .filter_map(|laddr| split_port(laddr).map(|(_, port)| port))
+                .collect();
+        }
+
+        // Signer state is copied from tendermint's one of last run, before it's
+        // overwritten below.
+        let signer_state = if builtin_signer {
+            let state_path = Path::new(&cm.db_dir).join(defined::SIGNER_STATE_FILE);
+            let tendermint_state_path = Path::new(&cm.priv_validator_state_file);
+
+            if !state_path.exists() && tendermint_state_path.exists() {
+                fs::copy(tendermint_state_path, &state_path)?;
+            }
+
+            Some(LastSignState::load(state_path)?)
+        } else {
+            validator_key.save(self.get_validator_key_path())?;
+
+            None
+        };
 
         let mut file = File::create(&cm.genesis_file)?;
-        let m = genesis.into_model();
-        let cs = serde_json::to_string_pretty(&m)?;
-        file.write_all(&cs.into_bytes())?;
+        file.write_all(&genesis.to_json()?)?;
 
         let validator_state = model::ValidatorState::default();
-        let mut file = File::create(cm.priv_validator_state_file)?;
+        let mut file = File::create(&cm.priv_validator_state_file)?;
         let m = validator_state.into_model();
         let cs = serde_json::to_string_pretty(&m)?;
         file.write_all(&cs.into_bytes())?;
 
+        if let Some(state) = signer_state {
+            let signer = Signer::new(validator_key, &genesis.chain_id, state);
+            self.signer = Some(signer.spawn(&cm.priv_validator_laddr)?);
+        }
+
         let p = Popen::create(
             &[
                 self.get_binary_path().as_os_str(),
@@ -199,7 +309,16 @@ impl Tendermint {
                 "node".as_ref(),
             ],
             PopenConfig::default(),
-        )?;
+        );
+
+        let p = match p {
+            Ok(p) => p,
+            Err(e) => {
+                self.stop_signer();
+
+                return Err(e.into());
+            }
+        };
 
         self.tendermint_child = Some(p);
 
@@ -213,9 +332,50 @@ impl Tendermint {
         validator_key: Keypair,
         genesis: Genesis<impl Serialize>,
     ) -> Result<()> {
+        self.remove_validator_key = false;
+
+        self.prepare_start(config, node_key, validator_key, genesis)
+    }
+
+    /// Start tendermint with encrypted validator key.
+    ///
+    /// Plaintext key file is only readable by owner, and is removed after
+    /// tendermint exit.
+    pub fn start_with_encrypted_key(
+        &mut self,
+        config: Config,
+        node_key: Keypair,
+        validator_key: &EncryptedKeypair,
+        passphrase: &str,
+        genesis: Genesis<impl Serialize>,
+    ) -> Result<()> {
+        let validator_key = validator_key.decrypt(passphrase)?;
+
+        self.remove_validator_key = true;
+
         self.prepare_start(config, node_key, validator_key, genesis)
     }
 
+    fn stop_signer(&mut self) {
+        if let Some(mut signer) = self.signer.take() {
+            signer.join();
+        }
+    }
+
+    fn release_ports(&mut self) {
+        config::release_ports(mem::take(&mut self.reserved_ports));
+    }
+
+    fn remove_plaintext_validator_key(&mut self) -> Result<()> {
+        let path = self.get_validator_key_path();
+
+        if self.remove_validator_key && path.exists() {
+            fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+
     pub fn stop(&mut self) -> Result<()> {
         let child = self
             .tendermint_child
@@ -224,6 +384,41 @@ impl Tendermint {
 
         child.terminate()?;
 
+        // A paused process doesn't handle SIGTERM until continued.
+        #[cfg(unix)]
+        Self::send_signal(child, libc::SIGCONT)?;
+
+        Ok(())
+    }
+
+    /// Pause tendermint process by `SIGSTOP`.
+    #[cfg(unix)]
+    pub fn pause(&mut self) -> Result<()> {
+        let child = self
+            .tendermint_child
+            .as_ref()
+            .ok_or(Error::NoTendermintStart)?;
+
+        Self::send_signal(child, libc::SIGSTOP)
+    }
+
+    /// Resume paused tendermint process by `SIGCONT`.
+    #[cfg(unix)]
+    pub fn resume(&mut self) -> Result<()> {
+        let child = self
+            .tendermint_child
+            .as_ref()
+            .ok_or(Error::NoTendermintStart)?;
+
+        Self::send_signal(child, libc::SIGCONT)
+    }
+
+    #[cfg(unix)]
+    fn send_signal(child: &Popen, signal: i32) -> Result<()> {
+        use subprocess::unix::PopenExt;
+
+        child.send_signal(signal)?;
+
         Ok(())
     }
 
@@ -246,6 +441,10 @@ impl Tendermint {
 
         child.wait()?;
 
+        self.stop_signer();
+        self.release_ports();
+        self.remove_plaintext_validator_key()?;
+
         Ok(())
     }
 }
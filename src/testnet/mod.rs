@@ -0,0 +1,598 @@
+mod proxy;
+pub use proxy::LinkCondition;
+
+use std::{
+    collections::BTreeMap,
+    net::{Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    thread,
+    time::{Duration, Instant},
+};
+
+use rand_core::OsRng;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    config::{self, P2PConfig, PeerAddress, PortStrategy, SentryTopology},
+    genesis::ValidatorInfo,
+    rpc, AlgorithmType, Config, Error, Genesis, GenesisBuilder, Keypair, Result, Tendermint,
+};
+
+/// Voting power of each testnet validator.
+const VALIDATOR_POWER: u64 = 10;
+
+/// Interval to poll RPC.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Max pause to redial a peer, so links recover soon after healed.
+const MAX_DIAL_PERIOD: i64 = 2;
+
+/// Local network of validators, all nodes run on this host
+///
+/// Validators have equal power, nodes listen P2P and RPC on free localhost
+/// ports. Peers are connected through in-process proxies, so links between
+/// nodes can be cut, delayed or throttled.
+pub struct Testnet<AppState = ()> {
+    /// Genesis shared by all nodes
+    pub genesis: Genesis<AppState>,
+
+    nodes: Vec<TestnetNode>,
+
+    /// Links between peered nodes, keyed by `(a, b)` with `a < b`.
+    links: BTreeMap<(usize, usize), proxy::Link>,
+
+    /// Ports of nodes, reserved until testnet dropped.
+    ports: ReservedPorts,
+}
+
+/// Node of testnet
+pub struct TestnetNode {
+    pub tendermint: Tendermint,
+
+    /// Config used by `Testnet::start`, can be changed before start.
+    pub config: Config,
+
+    pub node_key: Keypair,
+
+    pub validator_key: Keypair,
+}
+
+/// Ports reserved by port strategy, released on drop.
+struct ReservedPorts(Vec<u16>);
+
+impl Drop for ReservedPorts {
+    fn drop(&mut self) {
+        config::release_ports(self.0.drain(..));
+    }
+}
+
+/// Keys and ports of a node before config is built.
+struct NodeSlot {
+    node_key: Keypair,
+    validator_key: Keypair,
+    p2p_port: u16,
+    rpc_port: u16,
+}
+
+/// Genesis, configs and links of testnet before nodes are created.
+struct Plan {
+    genesis: Genesis<()>,
+    slots: Vec<NodeSlot>,
+    configs: Vec<Config>,
+    links: BTreeMap<(usize, usize), proxy::Link>,
+}
+
+impl NodeSlot {
+    /// Generate keys and pick ephemeral ports not in `used`.
+    fn generate(used: &mut Vec<u16>) -> Result<Self> {
+        Ok(Self {
+            node_key: Keypair::generate(AlgorithmType::Ed25519, OsRng),
+            validator_key: Keypair::generate(AlgorithmType::Ed25519, OsRng),
+            p2p_port: PortStrategy::Ephemeral.choose(0, used)?,
+            rpc_port: PortStrategy::Ephemeral.choose(0, used)?,
+        })
+    }
+
+    /// Real P2P address, peers are rewired to proxies later.
+    fn peer_address(&self) -> PeerAddress {
+        PeerAddress::new(&self.node_key.node_id(), "127.0.0.1", self.p2p_port)
+    }
+}
+
+impl Testnet<()> {
+    /// Generate keys, genesis and config of `n` validators.
+    pub fn new(n: usize) -> Result<Self> {
+        Self::from_plan(Self::plan_validators(n)?)
+    }
+
+    /// Generate `validators` validators, each hides behind `sentries` sentry
+    /// nodes.
+    ///
+    /// Validators are nodes `0..validators`, sentries of validator `i` follow
+    /// as nodes `validators + i * sentries..`. Sentries peer with all other
+    /// sentries, and they may bypass proxies of each other by pex.
+    pub fn with_sentries(validators: usize, sentries: usize) -> Result<Self> {
+        Self::from_plan(Self::plan_sentries(validators, sentries)?)
+    }
+
+    fn plan_validators(n: usize) -> Result<Plan> {
+        if n == 0 {
+            return Err(Error::InvalidTestnet(String::from(
+                "testnet needs at least one validator",
+            )));
+        }
+
+        let mut used = Vec::new();
+        let slots = (0..n)
+            .map(|_| NodeSlot::generate(&mut used))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Pex is disabled, otherwise nodes learn real addresses and bypass proxies.
+        let p2p = (0..n)
+            .map(|i| {
+                let persistent_peers = slots
+                    .iter()
+                    .enumerate()
+                    .filter(|(j, _)| *j != i)
+                    .map(|(_, s)| s.peer_address())
+                    .collect();
+
+                P2PConfig::default()
+                    .laddr(&format!("tcp://127.0.0.1:{}", slots[i].p2p_port))
+                    .persistent_peers(persistent_peers)
+                    .pex(false)
+            })
+            .collect();
+
+        Self::plan(slots, p2p, n)
+    }
+
+    fn plan_sentries(validators: usize, sentries: usize) -> Result<Plan> {
+        if validators == 0 || sentries == 0 {
+            return Err(Error::InvalidTestnet(String::from(
+                "sentry testnet needs at least one validator and one sentry",
+            )));
+        }
+
+        let n = validators * (1 + sentries);
+
+        let mut used = Vec::new();
+        let slots = (0..n)
+            .map(|_| NodeSlot::generate(&mut used))
+            .collect::<Result<Vec<_>>>()?;
+
+        let sentries_of = |i: usize| validators + i * sentries..validators + (i + 1) * sentries;
+
+        let mut p2p = vec![P2PConfig::default(); n];
+
+        for i in 0..validators {
+            let topology = SentryTopology::new(
+                slots[i].peer_address(),
+                slots[sentries_of(i)]
+                    .iter()
+                    .map(NodeSlot::peer_address)
+                    .collect(),
+            )
+            .public_peers(
+                (validators..n)
+                    .filter(|j| !sentries_of(i).contains(j))
+                    .map(|j| slots[j].peer_address())
+                    .collect(),
+            );
+
+            let base = P2PConfig::default().laddr("tcp://127.0.0.1:0");
+            let (validator, sentry_p2p) = topology.p2p_configs(base);
+
+            p2p[i] = validator;
+
+            for (j, config) in sentries_of(i).zip(sentry_p2p) {
+                p2p[j] = config;
+            }
+        }
+
+        Self::plan(slots, p2p, validators)
+    }
+
+    /// Plan testnet, first `validators` nodes are in genesis. Persistent
+    /// peers in `p2p` are rewired to proxies of links.
+    fn plan(slots: Vec<NodeSlot>, p2p: Vec<P2PConfig>, validators: usize) -> Result<Plan> {
+        let genesis = GenesisBuilder::new()
+            .chain_id("testnet")
+            .validators(
+                slots[..validators]
+                    .iter()
+                    .map(|s| {
+                        ValidatorInfo::new(s.validator_key.public_key.clone(), VALIDATOR_POWER)
+                    })
+                    .collect(),
+            )
+            .build()?;
+
+        let index_of = |id: &str| slots.iter().position(|s| s.node_key.node_id() == id);
+
+        let mut links: BTreeMap<(usize, usize), proxy::Link> = BTreeMap::new();
+        let mut configs = Vec::with_capacity(slots.len());
+
+        for (i, p2p) in p2p.into_iter().enumerate() {
+            let mut persistent_peers = Vec::with_capacity(p2p.persistent_peers.len());
+
+            for peer in &p2p.persistent_peers {
+                let port = match index_of(&peer.id) {
+                    Some(j) => {
+                        let target = SocketAddr::from((Ipv4Addr::LOCALHOST, slots[j].p2p_port));
+
+                        links
+                            .entry((i.min(j), i.max(j)))
+                            .or_default()
+                            .forward(target)?
+                    }
+                    None => peer.port,
+                };
+
+                persistent_peers.push(PeerAddress::new(&peer.id, &peer.host, port));
+            }
+
+            let p2p = p2p
+                .persistent_peers(persistent_peers)
+                .persistent_peers_max_dial_period(time::Duration::seconds(MAX_DIAL_PERIOD))
+                .local_net(true)
+                .allow_duplicate_ip(true);
+
+            let config = Config::default()
+                .moniker(&format!("node{}", i))
+                .rpc_laddr(&format!("tcp://127.0.0.1:{}", slots[i].rpc_port))
+                .p2p(p2p);
+
+            configs.push(config);
+        }
+
+        Ok(Plan {
+            genesis,
+            slots,
+            configs,
+            links,
+        })
+    }
+
+    /// Create tendermint of each planned node.
+    fn from_plan(plan: Plan) -> Result<Self> {
+        let ports = ReservedPorts(
+            plan.slots
+                .iter()
+                .flat_map(|s| [s.p2p_port, s.rpc_port])
+                .collect(),
+        );
+
+        let nodes = plan
+            .slots
+            .into_iter()
+            .zip(plan.configs)
+            .map(|(slot, config)| {
+                Ok(TestnetNode {
+                    tendermint: Tendermint::new()?,
+                    config,
+                    node_key: slot.node_key,
+                    validator_key: slot.validator_key,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            genesis: plan.genesis,
+            nodes,
+            links: plan.links,
+            ports,
+        })
+    }
+}
+
+impl<AppState> Testnet<AppState> {
+    /// Set app state of genesis.
+    pub fn app_state<A>(self, app_state: A) -> Testnet<A> {
+        let genesis = self.genesis;
+
+        Testnet {
+            genesis: Genesis {
+                genesis_time: genesis.genesis_time,
+                chain_id: genesis.chain_id,
+                initial_height: genesis.initial_height,
+                consensus_params: genesis.consensus_params,
+                validators: genesis.validators,
+                app_hash: genesis.app_hash,
+                app_state: Some(app_state),
+            },
+            nodes: self.nodes,
+            links: self.links,
+            ports: self.ports,
+        }
+    }
+
+    pub fn nodes(&self) -> &[TestnetNode] {
+        &self.nodes
+    }
+
+    pub fn nodes_mut(&mut self) -> &mut [TestnetNode] {
+        &mut self.nodes
+    }
+
+    pub fn node(&self, i: usize) -> Option<&TestnetNode> {
+        self.nodes.get(i)
+    }
+
+    pub fn node_mut(&mut self, i: usize) -> Option<&mut TestnetNode> {
+        self.nodes.get_mut(i)
+    }
+
+    fn link(&self, a: usize, b: usize) -> Result<&proxy::Link> {
+        self.links
+            .get(&(a.min(b), a.max(b)))
+            .ok_or(Error::NoTestnetLink(a, b))
+    }
+
+    /// Condition of link between node `a` and `b`, error if they aren't
+    /// peered.
+    pub fn link_condition(&self, a: usize, b: usize) -> Result<LinkCondition> {
+        Ok(self.link(a, b)?.condition())
+    }
+
+    /// Set condition of link between node `a` and `b`, error if they aren't
+    /// peered.
+    pub fn set_link_condition(&self, a: usize, b: usize, condition: LinkCondition) -> Result<()> {
+        self.link(a, b)?.set_condition(condition);
+
+        Ok(())
+    }
+
+    /// Cut links between nodes of different groups, nodes not in any group
+    /// are isolated.
+    pub fn partition(&self, groups: &[&[usize]]) {
+        let group_of = |i: usize| groups.iter().position(|g| g.contains(&i));
+
+        for (&(a, b), link) in &self.links {
+            let connected = matches!((group_of(a), group_of(b)), (Some(x), Some(y)) if x == y);
+
+            link.set_condition(LinkCondition::default().drop(!connected));
+        }
+    }
+
+    /// Restore all links to default condition.
+    pub fn heal(&self) {
+        for link in self.links.values() {
+            link.set_condition(LinkCondition::default());
+        }
+    }
+
+    /// Pause node `i` by `SIGSTOP`.
+    #[cfg(unix)]
+    pub fn pause(&mut self, i: usize) -> Result<()> {
+        self.node_mut(i)
+            .ok_or(Error::NoTestnetNode(i))?
+            .tendermint
+            .pause()
+    }
+
+    /// Resume node `i` by `SIGCONT`.
+    #[cfg(unix)]
+    pub fn resume(&mut self, i: usize) -> Result<()> {
+        self.node_mut(i)
+            .ok_or(Error::NoTestnetNode(i))?
+            .tendermint
+            .resume()
+    }
+
+    /// Stop all nodes and wait them exit.
+    pub fn stop(&mut self) -> Result<()> {
+        for node in &mut self.nodes {
+            node.stop()?;
+        }
+
+        for node in &mut self.nodes {
+            node.wait()?;
+        }
+
+        Ok(())
+    }
+
+    /// Wait until all nodes reach `height`.
+    pub fn wait_for_height(&self, height: u64, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+
+        for node in &self.nodes {
+            loop {
+                if matches!(node.latest_height(), Ok(h) if h >= height) {
+                    break;
+                }
+
+                if Instant::now() > deadline {
+                    return Err(Error::Timeout(format!(
+                        "{} doesn't reach height {}",
+                        node.config.moniker, height
+                    )));
+                }
+
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<AppState: Serialize + Clone> Testnet<AppState> {
+    /// Start all nodes, apps need to listen on each node's `app_path`.
+    pub fn start(&mut self) -> Result<()> {
+        self.genesis.validate()?;
+
+        for node in &mut self.nodes {
+            node.tendermint.start(
+                node.config.clone(),
+                node.node_key.clone(),
+                node.validator_key.clone(),
+                self.genesis.clone(),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TestnetNode {
+    pub fn node_id(&self) -> String {
+        self.node_key.node_id()
+    }
+
+    /// P2P listen address, error if node isn't started.
+    pub fn p2p_laddr(&self) -> Result<&str> {
+        self.tendermint.p2p_laddr()
+    }
+
+    /// RPC listen address, error if node isn't started.
+    pub fn rpc_laddr(&self) -> Result<&str> {
+        self.tendermint.rpc_laddr()
+    }
+
+    /// Unix socket for ABCI app of this node.
+    pub fn app_path(&self) -> PathBuf {
+        self.tendermint.get_app_path()
+    }
+
+    pub fn stop(&mut self) -> Result<()> {
+        self.tendermint.stop()
+    }
+
+    pub fn kill(&mut self) -> Result<()> {
+        self.tendermint.kill()
+    }
+
+    pub fn wait(&mut self) -> Result<()> {
+        self.tendermint.wait()
+    }
+
+    /// Query RPC, like `/status` or `/block?height=1`, returns `result`.
+    pub fn query(&self, path: &str) -> Result<Value> {
+        rpc::get(self.rpc_laddr()?, path)
+    }
+
+    /// Latest block height from `/status`.
+    pub fn latest_height(&self) -> Result<u64> {
+        let status = self.query("/status")?;
+
+        status["sync_info"]["latest_block_height"]
+            .as_str()
+            .and_then(|h| h.parse().ok())
+            .ok_or_else(|| Error::RpcError(String::from("no latest_block_height in status")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Error;
+
+    use super::Testnet;
+
+    #[test]
+    fn test_plan_validators() {
+        assert!(matches!(
+            Testnet::plan_validators(0),
+            Err(Error::InvalidTestnet(_))
+        ));
+
+        let plan = Testnet::plan_validators(3).unwrap();
+
+        assert_eq!(plan.genesis.chain_id, "testnet");
+        // Genesis shared by all nodes has validator key of each.
+        assert_eq!(plan.genesis.validators.len(), 3);
+        for (v, slot) in plan.genesis.validators.iter().zip(&plan.slots) {
+            assert_eq!(
+                v.public_key.as_ref(),
+                slot.validator_key.public_key.as_ref()
+            );
+        }
+        assert_eq!(plan.configs.len(), 3);
+        // Full mesh, a link per pair of nodes.
+        assert_eq!(plan.links.len(), 3);
+
+        let mut ports: Vec<u16> = plan
+            .slots
+            .iter()
+            .flat_map(|s| [s.p2p_port, s.rpc_port])
+            .collect();
+        ports.sort_unstable();
+        ports.dedup();
+        assert_eq!(ports.len(), 6);
+
+        for (i, config) in plan.configs.iter().enumerate() {
+            assert_eq!(config.moniker, format!("node{}", i));
+            assert_eq!(
+                config.p2p.laddr,
+                format!("tcp://127.0.0.1:{}", plan.slots[i].p2p_port)
+            );
+            assert!(!config.p2p.pex);
+            assert_eq!(config.p2p.persistent_peers.len(), 2);
+
+            for peer in &config.p2p.persistent_peers {
+                let j = plan
+                    .slots
+                    .iter()
+                    .position(|s| s.node_key.node_id() == peer.id)
+                    .unwrap();
+
+                assert_ne!(i, j);
+                // Dialed through proxy, not port of any node.
+                assert_eq!(peer.host, "127.0.0.1");
+                assert!(ports.binary_search(&peer.port).is_err());
+                assert!(plan.links.contains_key(&(i.min(j), i.max(j))));
+            }
+        }
+    }
+
+    #[test]
+    fn test_plan_sentries() {
+        assert!(Testnet::plan_sentries(1, 0).is_err());
+
+        // Validators 0 and 1, sentries 2, 3 of validator 0 and 4, 5 of 1.
+        let plan = Testnet::plan_sentries(2, 2).unwrap();
+
+        assert_eq!(plan.genesis.validators.len(), 2);
+        assert_eq!(plan.configs.len(), 6);
+
+        let peers_of = |i: usize| -> Vec<usize> {
+            plan.configs[i]
+                .p2p
+                .persistent_peers
+                .iter()
+                .map(|p| {
+                    plan.slots
+                        .iter()
+                        .position(|s| s.node_key.node_id() == p.id)
+                        .unwrap()
+                })
+                .collect()
+        };
+
+        assert_eq!(peers_of(0), vec![2, 3]);
+        assert_eq!(peers_of(1), vec![4, 5]);
+        assert_eq!(peers_of(2), vec![0, 3, 4, 5]);
+        assert_eq!(peers_of(5), vec![1, 4, 2, 3]);
+
+        let validator = &plan.configs[0].p2p;
+        assert!(!validator.pex);
+        assert_eq!(
+            validator.laddr,
+            format!("tcp://127.0.0.1:{}", plan.slots[0].p2p_port)
+        );
+
+        let sentry = &plan.configs[2].p2p;
+        assert!(sentry.pex);
+        assert_eq!(
+            sentry.private_peer_ids,
+            vec![plan.slots[0].node_key.node_id()]
+        );
+
+        // Validators aren't peered directly.
+        assert!(!plan.links.contains_key(&(0, 1)));
+        assert!(plan.links.contains_key(&(0, 2)));
+        assert!(plan.links.contains_key(&(2, 4)));
+    }
+}
@@ -0,0 +1,334 @@
+use std::{
+    collections::HashMap,
+    io::{Read, Write},
+    net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config::{self, define_build_mode_setter},
+    Result,
+};
+
+/// Size of read buffer of proxy.
+const BUFFER_SIZE: usize = 16 * 1024;
+
+/// Condition of link between two testnet nodes
+///
+/// Applies to both directions of the link.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LinkCondition {
+    /// Close connections and refuse new ones, like a network partition.
+    pub drop: bool,
+
+    /// Latency added to traffic.
+    pub delay: Duration,
+
+    /// Bandwidth limit in bytes/second, `None` is unlimited.
+    pub rate: Option<u64>,
+}
+
+impl LinkCondition {
+    define_build_mode_setter!(drop, bool);
+
+    define_build_mode_setter!(delay, Duration);
+
+    define_build_mode_setter!(rate, u64, option, throttle);
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    condition: Mutex<LinkCondition>,
+
+    /// Streams of alive proxied connections by id, to close them on drop.
+    conns: Mutex<HashMap<u64, [TcpStream; 2]>>,
+
+    next_id: AtomicU64,
+
+    stopped: AtomicBool,
+}
+
+impl Shared {
+    fn condition(&self) -> LinkCondition {
+        self.condition.lock().expect("lock poisoned").clone()
+    }
+
+    fn close_all(&self) {
+        for conn in self
+            .conns
+            .lock()
+            .expect("lock poisoned")
+            .drain()
+            .flat_map(|(_, c)| c)
+        {
+            let _ = conn.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// Proxied connection, removed from `Shared.conns` when both pipes exit.
+struct Conn {
+    id: u64,
+
+    shared: Arc<Shared>,
+}
+
+impl Drop for Conn {
+    fn drop(&mut self) {
+        self.shared
+            .conns
+            .lock()
+            .expect("lock poisoned")
+            .remove(&self.id);
+    }
+}
+
+/// In-process TCP proxies of a link between two nodes
+#[derive(Debug, Default)]
+pub(crate) struct Link {
+    shared: Arc<Shared>,
+
+    laddrs: Vec<SocketAddr>,
+}
+
+impl Link {
+    /// Listen on a free localhost port and forward connections to `target`.
+    ///
+    /// Ports reserved for nodes are skipped, returns port of listener.
+    pub(crate) fn forward(&mut self, target: SocketAddr) -> Result<u16> {
+        let (listener, laddr) = loop {
+            let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+            let laddr = listener.local_addr()?;
+
+            if config::reserve_port(laddr.port()) {
+                break (listener, laddr);
+            }
+        };
+
+        let shared = self.shared.clone();
+        thread::spawn(move || accept_loop(listener, target, shared));
+
+        self.laddrs.push(laddr);
+
+        Ok(laddr.port())
+    }
+
+    pub(crate) fn condition(&self) -> LinkCondition {
+        self.shared.condition()
+    }
+
+    pub(crate) fn set_condition(&self, condition: LinkCondition) {
+        let drop = condition.drop;
+
+        *self.shared.condition.lock().expect("lock poisoned") = condition;
+
+        if drop {
+            self.shared.close_all();
+        }
+    }
+}
+
+impl Drop for Link {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::SeqCst);
+
+        // Wake up blocked accept.
+        for laddr in &self.laddrs {
+            let _ = TcpStream::connect(laddr);
+        }
+
+        self.shared.close_all();
+
+        config::release_ports(self.laddrs.iter().map(SocketAddr::port));
+    }
+}
+
+fn accept_loop(listener: TcpListener, target: SocketAddr, shared: Arc<Shared>) {
+    for inbound in listener.incoming() {
+        if shared.stopped.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let inbound = match inbound {
+            Ok(s) => s,
+            Err(e) => {
+                log::debug!("Failed to accept proxy connection: {}", e);
+                continue;
+            }
+        };
+
+        if shared.condition().drop {
+            let _ = inbound.shutdown(Shutdown::Both);
+            continue;
+        }
+
+        if let Err(e) = proxy(inbound, target, &shared) {
+            log::debug!("Failed to proxy connection to {}: {}", target, e);
+        }
+    }
+}
+
+fn proxy(inbound: TcpStream, target: SocketAddr, shared: &Arc<Shared>) -> Result<()> {
+    let outbound = TcpStream::connect(target)?;
+
+    let id = shared.next_id.fetch_add(1, Ordering::SeqCst);
+    shared
+        .conns
+        .lock()
+        .expect("lock poisoned")
+        .insert(id, [inbound.try_clone()?, outbound.try_clone()?]);
+
+    let conn = Arc::new(Conn {
+        id,
+        shared: shared.clone(),
+    });
+
+    let (r1, w1) = (inbound.try_clone()?, outbound.try_clone()?);
+    let c1 = conn.clone();
+    thread::spawn(move || pipe(r1, w1, c1));
+
+    thread::spawn(move || pipe(outbound, inbound, conn));
+
+    Ok(())
+}
+
+/// Copy `from` to `to`, delay and throttle by condition of link.
+fn pipe(mut from: TcpStream, mut to: TcpStream, conn: Arc<Conn>) {
+    let (tx, rx) = mpsc::channel::<(Instant, Vec<u8>)>();
+
+    let shared = conn.shared.clone();
+    let writer = thread::spawn(move || {
+        for (received, data) in rx {
+            let condition = shared.condition();
+
+            if let Some(wait) = (received + condition.delay).checked_duration_since(Instant::now())
+            {
+                thread::sleep(wait);
+            }
+
+            if to.write_all(&data).is_err() {
+                break;
+            }
+
+            if let Some(rate) = condition.rate.filter(|r| *r > 0) {
+                thread::sleep(Duration::from_secs_f64(data.len() as f64 / rate as f64));
+            }
+        }
+
+        let _ = to.shutdown(Shutdown::Write);
+    });
+
+    let mut buf = [0u8; BUFFER_SIZE];
+
+    loop {
+        match from.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                if tx.send((Instant::now(), buf[..n].to_vec())).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    drop(tx);
+    let _ = writer.join();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Read, Write},
+        net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::{Link, LinkCondition};
+
+    fn echo_server() -> SocketAddr {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                thread::spawn(move || {
+                    let mut buf = [0u8; 64];
+                    while let Ok(n) = stream.read(&mut buf) {
+                        if n == 0 || stream.write_all(&buf[..n]).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        addr
+    }
+
+    #[test]
+    fn test_link_condition() {
+        let mut link = Link::default();
+        let port = link.forward(echo_server()).unwrap();
+
+        let mut conn = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        let mut buf = [0u8; 4];
+        conn.write_all(b"ping").unwrap();
+        conn.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"ping");
+
+        link.set_condition(LinkCondition::default().delay(Duration::from_millis(100)));
+        let start = Instant::now();
+        conn.write_all(b"pong").unwrap();
+        conn.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"pong");
+        // Delayed on both directions.
+        assert!(start.elapsed() >= Duration::from_millis(200));
+
+        link.set_condition(LinkCondition::default().drop(true));
+        assert!(matches!(conn.read(&mut buf), Ok(0) | Err(_)));
+
+        let mut conn = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        assert!(matches!(conn.read(&mut buf), Ok(0) | Err(_)));
+
+        link.set_condition(LinkCondition::default());
+        let mut conn = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        conn.write_all(b"back").unwrap();
+        conn.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"back");
+    }
+
+    #[test]
+    fn test_closed_connections_removed() {
+        let mut link = Link::default();
+        let port = link.forward(echo_server()).unwrap();
+
+        for _ in 0..10 {
+            let mut conn = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+            conn.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+            let mut buf = [0u8; 4];
+            conn.write_all(b"ping").unwrap();
+            conn.read_exact(&mut buf).unwrap();
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !link.shared.conns.lock().unwrap().is_empty() {
+            assert!(
+                Instant::now() < deadline,
+                "closed connections aren't removed"
+            );
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
@@ -1,6 +1,63 @@
-use std::fmt;
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io,
+    net::{Ipv4Addr, TcpListener},
+    path::Path,
+};
 
-use time::{OffsetDateTime, UtcOffset};
+use time::{Duration, OffsetDateTime, UtcOffset};
+
+/// Encode duration as nanoseconds integer string.
+///
+/// Tendermint 0.33, 0.34 and 0.37 all encode `time.Duration` in genesis
+/// as a nanoseconds integer string, like `"172800000000000000"` for the
+/// default max age of 2000 days.
+pub fn build_duration_nanos(d: Duration) -> String {
+    format!("{}", d.whole_nanoseconds())
+}
+
+/// Create file only readable and writable by owner, for secret keys.
+pub fn create_private_file(path: impl AsRef<Path>) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+
+    // Set mode on creation, so the file is never readable by others.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        options.mode(0o600);
+    }
+
+    let file = options.open(path)?;
+
+    // Mode only applies to new files, restrict an existing one too.
+    #[cfg(unix)]
+    {
+        use std::{fs::Permissions, os::unix::fs::PermissionsExt};
+
+        file.set_permissions(Permissions::from_mode(0o600))?;
+    }
+
+    Ok(file)
+}
+
+/// Parse RFC 3339 time, like `genesis_time` of tendermint.
+#[cfg(feature = "serde")]
+pub fn parse_rfc3339(s: &str) -> crate::Result<OffsetDateTime> {
+    OffsetDateTime::parse(s, &time::format_description::well_known::Rfc3339)
+        .map_err(|_| crate::Error::InvalidGenesis(format!("invalid time: {}", s)))
+}
+
+/// Ask OS for a free TCP port on localhost.
+///
+/// Port is released before return, so it may be taken by others in between.
+pub fn free_port() -> io::Result<u16> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+
+    Ok(listener.local_addr()?.port())
+}
 
 pub fn to_rfc3339_nanos(t: OffsetDateTime) -> String {
     // yyyy-mm-ddThh:mm:ssZ
@@ -46,3 +103,219 @@ pub fn fmt_as_rfc3339_nanos(t: OffsetDateTime, f: &mut impl fmt::Write) -> fmt::
         )
     }
 }
+
+const NANOS_PER_MICRO: u128 = 1_000;
+const NANOS_PER_MILLI: u128 = 1_000_000;
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+
+/// Format duration like golang's `time.Duration.String()`, e.g. `1h2m3.5s`.
+pub fn format_go_duration(d: Duration) -> String {
+    let nanos = d.whole_nanoseconds();
+
+    if nanos == 0 {
+        return String::from("0s");
+    }
+
+    let sign = if nanos < 0 { "-" } else { "" };
+    let u = nanos.unsigned_abs();
+
+    if u < NANOS_PER_MICRO {
+        return format!("{}{}ns", sign, u);
+    }
+
+    if u < NANOS_PER_MILLI {
+        return format!("{}{}µs", sign, fmt_frac(u, NANOS_PER_MICRO));
+    }
+
+    if u < NANOS_PER_SECOND {
+        return format!("{}{}ms", sign, fmt_frac(u, NANOS_PER_MILLI));
+    }
+
+    let seconds = fmt_frac(u % (60 * NANOS_PER_SECOND), NANOS_PER_SECOND);
+    let minutes = u / (60 * NANOS_PER_SECOND);
+
+    if minutes == 0 {
+        format!("{}{}s", sign, seconds)
+    } else if minutes < 60 {
+        format!("{}{}m{}s", sign, minutes, seconds)
+    } else {
+        format!("{}{}h{}m{}s", sign, minutes / 60, minutes % 60, seconds)
+    }
+}
+
+/// `v / unit` as decimal, trailing zeros of fraction removed.
+fn fmt_frac(v: u128, unit: u128) -> String {
+    let int = v / unit;
+    let frac = v % unit;
+
+    if frac == 0 {
+        return format!("{}", int);
+    }
+
+    let width = unit.to_string().len() - 1;
+    let frac = format!("{:0width$}", frac, width = width);
+
+    format!("{}.{}", int, frac.trim_end_matches('0'))
+}
+
+/// Parse golang duration string, like `300ms`, `-1.5h` or `2h45m`.
+///
+/// Units are `ns`, `us` (or `µs`), `ms`, `s`, `m` and `h`.
+pub fn parse_go_duration(s: &str) -> crate::Result<Duration> {
+    let invalid = || crate::Error::InvalidDuration(String::from(s));
+
+    let (neg, mut rest) = match s.strip_prefix('-') {
+        Some(r) => (true, r),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+
+    if rest == "0" {
+        return Ok(Duration::ZERO);
+    }
+
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut total = 0u128;
+
+    while !rest.is_empty() {
+        let int_len = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let int = &rest[..int_len];
+        rest = &rest[int_len..];
+
+        let mut frac = "";
+        if let Some(r) = rest.strip_prefix('.') {
+            let frac_len = r.find(|c: char| !c.is_ascii_digit()).unwrap_or(r.len());
+            frac = &r[..frac_len];
+            rest = &r[frac_len..];
+        }
+
+        if int.is_empty() && frac.is_empty() {
+            return Err(invalid());
+        }
+
+        let unit_len = rest
+            .find(|c: char| c == '.' || c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let unit = match &rest[..unit_len] {
+            "ns" => 1,
+            "us" | "µs" | "μs" => NANOS_PER_MICRO,
+            "ms" => NANOS_PER_MILLI,
+            "s" => NANOS_PER_SECOND,
+            "m" => 60 * NANOS_PER_SECOND,
+            "h" => 3600 * NANOS_PER_SECOND,
+            _ => return Err(invalid()),
+        };
+        rest = &rest[unit_len..];
+
+        let int: u128 = if int.is_empty() {
+            0
+        } else {
+            int.parse().map_err(|_| invalid())?
+        };
+
+        // Digits beyond nanosecond precision are dropped.
+        let mut frac_nanos = 0u128;
+        let mut scale = unit;
+        for c in frac.bytes() {
+            scale /= 10;
+            frac_nanos += (c - b'0') as u128 * scale;
+        }
+
+        total = int
+            .checked_mul(unit)
+            .and_then(|v| v.checked_add(frac_nanos))
+            .and_then(|v| v.checked_add(total))
+            .filter(|&v| v <= i64::MAX as u128)
+            .ok_or_else(invalid)?;
+    }
+
+    let nanos = total as i64;
+
+    Ok(Duration::nanoseconds(if neg { -nanos } else { nanos }))
+}
+
+/// Golang duration string, used by serde of config durations.
+#[cfg(feature = "serde")]
+pub mod serde_go_duration {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use time::Duration;
+
+    use super::{format_go_duration, parse_go_duration};
+
+    pub fn serialize<S: Serializer>(d: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_go_duration(*d))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let s = String::deserialize(deserializer)?;
+
+        parse_go_duration(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::Duration;
+
+    use super::{format_go_duration, parse_go_duration};
+
+    #[test]
+    fn test_go_duration() {
+        for (d, s) in [
+            (Duration::ZERO, "0s"),
+            (Duration::nanoseconds(1), "1ns"),
+            (Duration::microseconds(1), "1µs"),
+            (Duration::milliseconds(500), "500ms"),
+            (Duration::milliseconds(1500), "1.5s"),
+            (Duration::seconds(90), "1m30s"),
+            (Duration::hours(168), "168h0m0s"),
+            (Duration::milliseconds(-250), "-250ms"),
+        ] {
+            assert_eq!(format_go_duration(d), s);
+            assert_eq!(parse_go_duration(s).unwrap(), d);
+        }
+
+        assert_eq!(
+            parse_go_duration("1h2m3.5s").unwrap(),
+            Duration::milliseconds(3_723_500)
+        );
+        assert_eq!(parse_go_duration("0").unwrap(), Duration::ZERO);
+
+        for s in ["", "1", "1d", "ms", "-", "1.s.5"] {
+            assert!(parse_go_duration(s).is_err(), "{}", s);
+        }
+    }
+
+    #[test]
+    fn test_go_duration_precision_and_negative() {
+        // No rounding to whole units.
+        for (d, s) in [
+            (Duration::nanoseconds(1_500), "1.5µs"),
+            (Duration::nanoseconds(1_000_001), "1.000001ms"),
+            (Duration::nanoseconds(3_600_000_000_001), "1h0m0.000000001s"),
+            (Duration::nanoseconds(-1_500), "-1.5µs"),
+            (Duration::seconds(-90), "-1m30s"),
+            (Duration::milliseconds(-5_400_500), "-1h30m0.5s"),
+        ] {
+            assert_eq!(format_go_duration(d), s);
+            assert_eq!(parse_go_duration(s).unwrap(), d);
+        }
+
+        // Digits beyond nanosecond are dropped.
+        assert_eq!(
+            parse_go_duration("1.0000000019s").unwrap(),
+            Duration::nanoseconds(1_000_000_001)
+        );
+        assert_eq!(parse_go_duration("-1.5h").unwrap(), Duration::minutes(-90));
+        assert_eq!(
+            parse_go_duration("+250ms").unwrap(),
+            Duration::milliseconds(250)
+        );
+        assert_eq!(parse_go_duration("-0").unwrap(), Duration::ZERO);
+        assert!(parse_go_duration("9223372037s").is_err());
+    }
+}